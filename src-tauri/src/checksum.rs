@@ -0,0 +1,778 @@
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use md5::Md5;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::ipc::Channel;
+
+use crate::operations::{OperationControl, OperationRegistry, ProgressThrottle};
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+const BUFFER_SIZE: usize = 1024 * 1024;
+
+pub enum ChecksumError {
+    InvalidPath(String),
+    InvalidManifest { line: usize, reason: String },
+    UnknownAlgorithm(String),
+    Cancelled,
+    IoError(String),
+}
+
+impl serde::Serialize for ChecksumError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let err_msg = match self {
+            ChecksumError::InvalidPath(path) => format!("Invalid Path: {}", path),
+            ChecksumError::InvalidManifest { line, reason } => {
+                format!("Invalid Manifest: line {}: {}", line, reason)
+            }
+            ChecksumError::UnknownAlgorithm(path) => {
+                format!("Unknown checksum type: {}", path)
+            }
+            ChecksumError::Cancelled => "Cancelled".to_string(),
+            ChecksumError::IoError(reason) => format!("IO Error: {}", reason),
+        };
+
+        serializer.serialize_str(err_msg.as_str())
+    }
+}
+
+impl From<io::Error> for ChecksumError {
+    fn from(err: io::Error) -> Self {
+        ChecksumError::IoError(err.to_string())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ChecksumAlgorithm {
+    /// `sha256sum` format.
+    Sha256,
+    /// `md5sum` format.
+    Md5,
+    /// Simple File Verification: CRC32 per file.
+    Crc32,
+}
+
+impl ChecksumAlgorithm {
+    /// Guesses the algorithm from a manifest's extension, or from names such
+    /// as `SHA256SUMS` and `md5sum.txt`.
+    pub fn from_manifest(path: &Path) -> Option<Self> {
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("sha256" | "sha256sum" | "sha256sums") => return Some(ChecksumAlgorithm::Sha256),
+            Some("md5" | "md5sum" | "md5sums") => return Some(ChecksumAlgorithm::Md5),
+            Some("sfv") => return Some(ChecksumAlgorithm::Crc32),
+            _ => {}
+        }
+
+        let name = path.file_name()?.to_string_lossy().to_ascii_lowercase();
+        if name.starts_with("sha256sum") {
+            Some(ChecksumAlgorithm::Sha256)
+        } else if name.starts_with("md5sum") {
+            Some(ChecksumAlgorithm::Md5)
+        } else {
+            None
+        }
+    }
+
+    fn digest_len(self) -> usize {
+        match self {
+            ChecksumAlgorithm::Sha256 => 32,
+            ChecksumAlgorithm::Md5 => 16,
+            ChecksumAlgorithm::Crc32 => 4,
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChecksumProgress {
+    pub current_file: PathBuf,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    pub files_done: u64,
+    pub files_total: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChecksumFailure {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChecksumMismatch {
+    pub path: PathBuf,
+    pub expected: String,
+    pub actual: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerateReport {
+    pub manifest: PathBuf,
+    pub files: u64,
+    pub bytes: u64,
+    /// Files left out of the manifest because they could not be read.
+    pub failures: Vec<ChecksumFailure>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyReport {
+    pub algorithm: ChecksumAlgorithm,
+    /// Files whose checksum matched.
+    pub verified: u64,
+    pub mismatched: Vec<ChecksumMismatch>,
+    /// Listed in the manifest but not on disk.
+    pub missing: Vec<PathBuf>,
+    /// Not listed, but in the deepest directory that holds every listed file.
+    pub extra: Vec<PathBuf>,
+    /// Listed, but could not be checked or read.
+    pub failures: Vec<ChecksumFailure>,
+}
+
+/// One manifest line: a path relative to the manifest's directory and its digest.
+struct ManifestEntry {
+    relative: String,
+    digest: Vec<u8>,
+}
+
+/// Writes and checks checksum manifests in the formats of `sha256sum`,
+/// `md5sum` and SFV.
+///
+/// Paths in a manifest are relative to the directory that holds it and use `/`
+/// as separator, so a manifest stays valid when the tree is moved or copied.
+pub struct ChecksumEngine {
+    control: Arc<OperationControl>,
+}
+
+impl ChecksumEngine {
+    pub fn new(control: Arc<OperationControl>) -> Self {
+        Self { control }
+    }
+
+    /// Hashes every file under `root` and writes the manifest to `manifest`,
+    /// which must be in `root` or one of its parents.
+    pub fn generate<F>(
+        &self,
+        root: &Path,
+        manifest: &Path,
+        algorithm: ChecksumAlgorithm,
+        on_progress: F,
+    ) -> Result<GenerateReport, ChecksumError>
+    where
+        F: Fn(ChecksumProgress) + Sync,
+    {
+        if !root.is_dir() {
+            return Err(ChecksumError::InvalidPath(root.display().to_string()));
+        }
+        let base = manifest
+            .parent()
+            .filter(|base| root.starts_with(base))
+            .ok_or_else(|| ChecksumError::InvalidPath(manifest.display().to_string()))?;
+
+        let mut files = Vec::new();
+        let mut failures = Vec::new();
+        collect_files(root, manifest, &mut files, &mut failures);
+        files.sort();
+
+        let digests = self.hash_all(&files, algorithm, &on_progress);
+        if self.control.is_cancelled() {
+            return Err(ChecksumError::Cancelled);
+        }
+
+        let mut entries = Vec::new();
+        let mut bytes = 0;
+        for ((path, size), digest) in files.into_iter().zip(digests) {
+            match digest {
+                Ok(digest) => {
+                    bytes += size;
+                    entries.push(ManifestEntry {
+                        relative: relative_name(&path, base),
+                        digest,
+                    });
+                }
+                Err(err) => failures.push(ChecksumFailure {
+                    path,
+                    reason: err.to_string(),
+                }),
+            }
+        }
+
+        write_manifest(manifest, algorithm, &entries)?;
+        Ok(GenerateReport {
+            manifest: manifest.to_path_buf(),
+            files: entries.len() as u64,
+            bytes,
+            failures,
+        })
+    }
+
+    /// Checks the files listed in `manifest`. The algorithm is taken from the
+    /// manifest's extension unless given.
+    pub fn verify<F>(
+        &self,
+        manifest: &Path,
+        algorithm: Option<ChecksumAlgorithm>,
+        on_progress: F,
+    ) -> Result<VerifyReport, ChecksumError>
+    where
+        F: Fn(ChecksumProgress) + Sync,
+    {
+        let base = manifest
+            .parent()
+            .filter(|_| manifest.is_file())
+            .ok_or_else(|| ChecksumError::InvalidPath(manifest.display().to_string()))?;
+        let algorithm = algorithm
+            .or_else(|| ChecksumAlgorithm::from_manifest(manifest))
+            .ok_or_else(|| ChecksumError::UnknownAlgorithm(manifest.display().to_string()))?;
+        let entries = parse_manifest(&fs::read_to_string(manifest)?, algorithm)?;
+
+        let mut report = VerifyReport {
+            algorithm,
+            verified: 0,
+            mismatched: Vec::new(),
+            missing: Vec::new(),
+            extra: Vec::new(),
+            failures: Vec::new(),
+        };
+        let mut present = Vec::new();
+        let mut expected = Vec::new();
+        for entry in entries {
+            let path = base.join(&entry.relative);
+            match fs::metadata(&path) {
+                Ok(metadata) if metadata.is_file() => {
+                    present.push((path, metadata.len()));
+                    expected.push(entry.digest);
+                }
+                Ok(_) => report.failures.push(ChecksumFailure {
+                    path,
+                    reason: "not a regular file".to_string(),
+                }),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => report.missing.push(path),
+                Err(err) => report.failures.push(ChecksumFailure {
+                    path,
+                    reason: err.to_string(),
+                }),
+            }
+        }
+        let covered = common_dir(
+            report
+                .missing
+                .iter()
+                .chain(present.iter().map(|(path, _)| path)),
+        );
+
+        let digests = self.hash_all(&present, algorithm, &on_progress);
+        if self.control.is_cancelled() {
+            return Err(ChecksumError::Cancelled);
+        }
+
+        let listed: HashSet<PathBuf> = present.iter().map(|(path, _)| normalize(path)).collect();
+        for (((path, _), digest), expected) in present.into_iter().zip(digests).zip(expected) {
+            match digest {
+                Ok(actual) if actual == expected => report.verified += 1,
+                Ok(actual) => report.mismatched.push(ChecksumMismatch {
+                    path,
+                    expected: to_hex(&expected),
+                    actual: to_hex(&actual),
+                }),
+                Err(err) => report.failures.push(ChecksumFailure {
+                    path,
+                    reason: err.to_string(),
+                }),
+            }
+        }
+
+        let mut on_disk = Vec::new();
+        if let Some(covered) = covered {
+            collect_files(&covered, manifest, &mut on_disk, &mut Vec::new());
+        }
+        report.extra = on_disk
+            .into_iter()
+            .map(|(path, _)| path)
+            .filter(|path| !listed.contains(&normalize(path)))
+            .collect();
+        report.extra.sort();
+
+        Ok(report)
+    }
+
+    fn hash_all<F>(
+        &self,
+        files: &[(PathBuf, u64)],
+        algorithm: ChecksumAlgorithm,
+        on_progress: &F,
+    ) -> Vec<io::Result<Vec<u8>>>
+    where
+        F: Fn(ChecksumProgress) + Sync,
+    {
+        let throttle = ProgressThrottle::new(PROGRESS_INTERVAL);
+        let bytes_total = files.iter().map(|(_, size)| size).sum();
+        let files_total = files.len() as u64;
+        let bytes_done = AtomicU64::new(0);
+        let files_done = AtomicU64::new(0);
+
+        files
+            .par_iter()
+            .map(|(path, _)| {
+                let digest = self.hash_file(path, algorithm, |read| {
+                    let bytes = bytes_done.fetch_add(read, Ordering::Relaxed) + read;
+                    if throttle.ready() {
+                        on_progress(ChecksumProgress {
+                            current_file: path.clone(),
+                            bytes_done: bytes,
+                            bytes_total,
+                            files_done: files_done.load(Ordering::Relaxed),
+                            files_total,
+                        });
+                    }
+                });
+                files_done.fetch_add(1, Ordering::Relaxed);
+                digest
+            })
+            .collect()
+    }
+
+    fn hash_file(
+        &self,
+        path: &Path,
+        algorithm: ChecksumAlgorithm,
+        on_read: impl Fn(u64),
+    ) -> io::Result<Vec<u8>> {
+        let mut file = File::open(path)?;
+        let mut hasher = Hasher::new(algorithm);
+        let mut buffer = vec![0; BUFFER_SIZE];
+        loop {
+            if !self.control.checkpoint() {
+                return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+            }
+            let read = file.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+            on_read(read as u64);
+        }
+        Ok(hasher.finalize())
+    }
+}
+
+enum Hasher {
+    Sha256(Sha256),
+    Md5(Md5),
+    Crc32(crc32fast::Hasher),
+}
+
+impl Hasher {
+    fn new(algorithm: ChecksumAlgorithm) -> Self {
+        match algorithm {
+            ChecksumAlgorithm::Sha256 => Hasher::Sha256(Sha256::new()),
+            ChecksumAlgorithm::Md5 => Hasher::Md5(Md5::new()),
+            ChecksumAlgorithm::Crc32 => Hasher::Crc32(crc32fast::Hasher::new()),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Sha256(hasher) => hasher.update(data),
+            Hasher::Md5(hasher) => hasher.update(data),
+            Hasher::Crc32(hasher) => hasher.update(data),
+        }
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        match self {
+            Hasher::Sha256(hasher) => hasher.finalize().to_vec(),
+            Hasher::Md5(hasher) => hasher.finalize().to_vec(),
+            Hasher::Crc32(hasher) => hasher.finalize().to_be_bytes().to_vec(),
+        }
+    }
+}
+
+/// Regular files under `dir`, with their sizes, leaving out the manifest itself.
+/// Symlinks are not followed.
+fn collect_files(
+    dir: &Path,
+    manifest: &Path,
+    files: &mut Vec<(PathBuf, u64)>,
+    failures: &mut Vec<ChecksumFailure>,
+) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) => {
+            failures.push(ChecksumFailure {
+                path: dir.to_path_buf(),
+                reason: err.to_string(),
+            });
+            return;
+        }
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        // DirEntry::metadata does not traverse symlinks.
+        match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => collect_files(&path, manifest, files, failures),
+            Ok(metadata) if metadata.is_file() && normalize(&path) != normalize(manifest) => {
+                files.push((path, metadata.len()))
+            }
+            Ok(_) => {}
+            Err(err) => failures.push(ChecksumFailure {
+                path,
+                reason: err.to_string(),
+            }),
+        }
+    }
+}
+
+/// The deepest directory holding every path, or `None` without paths.
+fn common_dir<'a>(paths: impl Iterator<Item = &'a PathBuf>) -> Option<PathBuf> {
+    let mut common: Option<PathBuf> = None;
+    for path in paths {
+        let dir = normalize(path.parent()?);
+        common = Some(match common {
+            None => dir,
+            Some(common) => common
+                .components()
+                .zip(dir.components())
+                .take_while(|(left, right)| left == right)
+                .map(|(component, _)| component)
+                .collect(),
+        });
+    }
+    common
+}
+
+fn relative_name(path: &Path, base: &Path) -> String {
+    let relative = path.strip_prefix(base).unwrap_or(path);
+    relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Drops `.` components so paths from the manifest and from the walk compare equal.
+fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|component| *component != Component::CurDir)
+        .collect()
+}
+
+fn write_manifest(
+    manifest: &Path,
+    algorithm: ChecksumAlgorithm,
+    entries: &[ManifestEntry],
+) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(manifest)?);
+    for entry in entries {
+        match algorithm {
+            ChecksumAlgorithm::Crc32 => writeln!(
+                writer,
+                "{} {}",
+                entry.relative,
+                to_hex(&entry.digest).to_ascii_uppercase()
+            )?,
+            // Like coreutils, a leading backslash marks names with escaped characters.
+            _ if entry.relative.contains(['\\', '\n', '\r']) => {
+                let escaped = entry
+                    .relative
+                    .replace('\\', "\\\\")
+                    .replace('\n', "\\n")
+                    .replace('\r', "\\r");
+                writeln!(writer, "\\{}  {}", to_hex(&entry.digest), escaped)?
+            }
+            _ => writeln!(writer, "{}  {}", to_hex(&entry.digest), entry.relative)?,
+        }
+    }
+    writer.flush()
+}
+
+fn parse_manifest(
+    contents: &str,
+    algorithm: ChecksumAlgorithm,
+) -> Result<Vec<ManifestEntry>, ChecksumError> {
+    let mut entries = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let invalid = |reason: &str| ChecksumError::InvalidManifest {
+            line: index + 1,
+            reason: reason.to_string(),
+        };
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let (relative, digest) = if algorithm == ChecksumAlgorithm::Crc32 {
+            if line.starts_with(';') {
+                continue;
+            }
+            let (name, crc) = line
+                .trim_end()
+                .rsplit_once(' ')
+                .ok_or_else(|| invalid("expected a name and a CRC32"))?;
+            // SFV files written on Windows use backslashes as separators.
+            (name.trim_end().replace('\\', "/"), crc)
+        } else {
+            if line.starts_with('#') {
+                continue;
+            }
+            let (line, escaped) = match line.strip_prefix('\\') {
+                Some(rest) => (rest, true),
+                None => (line, false),
+            };
+            let (digest, name) = line
+                .split_once(' ')
+                .ok_or_else(|| invalid("expected a digest and a name"))?;
+            // One space and `*` for binary mode, or two spaces for text mode.
+            let name = name
+                .strip_prefix(['*', ' '])
+                .ok_or_else(|| invalid("expected a digest and a name"))?;
+            let name = if escaped {
+                unescape(name).ok_or_else(|| invalid("bad escape sequence"))?
+            } else {
+                name.to_string()
+            };
+            (name, digest)
+        };
+
+        let digest = from_hex(digest)
+            .filter(|digest| digest.len() == algorithm.digest_len())
+            .ok_or_else(|| invalid("malformed checksum"))?;
+        if relative.is_empty() {
+            return Err(invalid("missing file name"));
+        }
+        entries.push(ManifestEntry { relative, digest });
+    }
+    Ok(entries)
+}
+
+fn unescape(name: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(name.len());
+    let mut chars = name.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next()? {
+            '\\' => unescaped.push('\\'),
+            'n' => unescaped.push('\n'),
+            'r' => unescaped.push('\r'),
+            _ => return None,
+        }
+    }
+    Some(unescaped)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+#[tauri::command]
+pub async fn generate_checksums(
+    str_root: String,
+    str_manifest: String,
+    algorithm: ChecksumAlgorithm,
+    operation_id: String,
+    on_progress: Channel<ChecksumProgress>,
+    registry: tauri::State<'_, OperationRegistry>,
+) -> Result<GenerateReport, ChecksumError> {
+    let engine = ChecksumEngine::new(registry.register(&operation_id));
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        engine.generate(
+            Path::new(&str_root),
+            Path::new(&str_manifest),
+            algorithm,
+            |progress| {
+                let _ = on_progress.send(progress);
+            },
+        )
+    })
+    .await;
+
+    registry.finish(&operation_id);
+    result.map_err(|err| ChecksumError::IoError(err.to_string()))?
+}
+
+#[tauri::command]
+pub async fn verify_checksums(
+    str_manifest: String,
+    algorithm: Option<ChecksumAlgorithm>,
+    operation_id: String,
+    on_progress: Channel<ChecksumProgress>,
+    registry: tauri::State<'_, OperationRegistry>,
+) -> Result<VerifyReport, ChecksumError> {
+    let engine = ChecksumEngine::new(registry.register(&operation_id));
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        engine.verify(Path::new(&str_manifest), algorithm, |progress| {
+            let _ = on_progress.send(progress);
+        })
+    })
+    .await;
+
+    registry.finish(&operation_id);
+    result.map_err(|err| ChecksumError::IoError(err.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine() -> ChecksumEngine {
+        ChecksumEngine::new(Arc::default())
+    }
+
+    fn write(root: &Path, files: &[(&str, &str)]) {
+        for (name, contents) in files {
+            let path = root.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+    }
+
+    #[test]
+    fn parses_coreutils_and_sfv_lines() {
+        let digest = "d41d8cd98f00b204e9800998ecf8427e";
+        let contents = format!(
+            "# comment\n{d}  plain.txt\n{d} *binary.bin\n\\{d}  dir/back\\\\slash\\nnewline\r\n\n",
+            d = digest
+        );
+        let entries = parse_manifest(&contents, ChecksumAlgorithm::Md5)
+            .ok()
+            .unwrap();
+        let names: Vec<&str> = entries
+            .iter()
+            .map(|entry| entry.relative.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            ["plain.txt", "binary.bin", "dir/back\\slash\nnewline"]
+        );
+        assert_eq!(to_hex(&entries[0].digest), digest);
+
+        let sfv = "; generated\nsub\\file name.txt 0A1B2C3D\n";
+        let entries = parse_manifest(sfv, ChecksumAlgorithm::Crc32).ok().unwrap();
+        assert_eq!(entries[0].relative, "sub/file name.txt");
+        assert_eq!(entries[0].digest, [0x0a, 0x1b, 0x2c, 0x3d]);
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        for line in [
+            "abc  file",
+            "d41d8cd98f00b204e9800998ecf8427e",
+            "\\d41d8cd98f00b204e9800998ecf8427e  bad\\x",
+        ] {
+            let result = parse_manifest(line, ChecksumAlgorithm::Md5);
+            assert!(matches!(
+                result,
+                Err(ChecksumError::InvalidManifest { line: 1, .. })
+            ));
+        }
+        assert_eq!(unescape("a\\\\b\\nc"), Some("a\\b\nc".to_string()));
+        assert_eq!(unescape("trailing\\"), None);
+    }
+
+    #[test]
+    fn recognizes_manifests_without_an_extension() {
+        let guess = |name: &str| ChecksumAlgorithm::from_manifest(Path::new(name));
+        assert!(guess("SHA256SUMS") == Some(ChecksumAlgorithm::Sha256));
+        assert!(guess("md5sum.txt") == Some(ChecksumAlgorithm::Md5));
+        assert!(guess("release.SFV") == Some(ChecksumAlgorithm::Crc32));
+        assert!(guess("notes.txt").is_none());
+    }
+
+    #[test]
+    fn manifest_in_a_parent_only_covers_the_hashed_tree() {
+        let root = tempfile::tempdir().unwrap();
+        write(
+            root.path(),
+            &[
+                ("photos/a.jpg", "a"),
+                ("photos/sub/b.jpg", "b"),
+                ("c.txt", "c"),
+                ("other/b.txt", "b"),
+            ],
+        );
+        let manifest = root.path().join("photos.sha256");
+        let generated = engine()
+            .generate(
+                &root.path().join("photos"),
+                &manifest,
+                ChecksumAlgorithm::Sha256,
+                |_| {},
+            )
+            .ok()
+            .unwrap();
+        assert_eq!(generated.files, 2);
+
+        write(root.path(), &[("photos/new.jpg", "new")]);
+        let report = engine().verify(&manifest, None, |_| {}).ok().unwrap();
+        assert_eq!(report.verified, 2);
+        assert_eq!(report.extra, [root.path().join("photos/new.jpg")]);
+    }
+
+    #[test]
+    fn only_the_manifest_itself_is_left_out() {
+        let root = tempfile::tempdir().unwrap();
+        write(
+            root.path(),
+            &[("data.bin", "data"), ("user.md5", "not a manifest")],
+        );
+        let manifest = root.path().join("SHA256SUMS");
+        let generated = engine()
+            .generate(root.path(), &manifest, ChecksumAlgorithm::Sha256, |_| {})
+            .ok()
+            .unwrap();
+        assert_eq!(generated.files, 2);
+
+        let report = engine().verify(&manifest, None, |_| {}).ok().unwrap();
+        assert_eq!(report.verified, 2);
+        assert!(report.extra.is_empty());
+    }
+
+    #[test]
+    fn unreadable_entries_are_failures_not_missing() {
+        let root = tempfile::tempdir().unwrap();
+        write(root.path(), &[("file", "x")]);
+        let manifest = root.path().join("sums.md5");
+        let digest = "d41d8cd98f00b204e9800998ecf8427e";
+        fs::write(
+            &manifest,
+            format!("{d}  gone\n{d}  file/inner\n", d = digest),
+        )
+        .unwrap();
+
+        let report = engine().verify(&manifest, None, |_| {}).ok().unwrap();
+        assert_eq!(report.missing, [root.path().join("gone")]);
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.failures[0].path, root.path().join("file/inner"));
+    }
+}
@@ -0,0 +1,911 @@
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use similar::{Algorithm, ChangeTag, TextDiff};
+use tauri::ipc::Channel;
+
+use crate::metadata::{unix_millis, EntryKind};
+use crate::operations::{OperationControl, OperationRegistry};
+
+const BLOCK_SIZE: usize = 64 * 1024;
+/// Bytes inspected for NUL when deciding whether a file is text.
+const SNIFF_SIZE: usize = 8 * 1024;
+const MAX_BYTE_RANGES: usize = 10_000;
+const TEXT_DIFF_TIMEOUT: Duration = Duration::from_secs(5);
+/// FAT and some network shares store modification times in 2 second steps.
+const MODIFIED_TOLERANCE_MS: u64 = 2000;
+
+pub enum DiffError {
+    InvalidPath(String),
+    Cancelled,
+    IoError(String),
+}
+
+impl serde::Serialize for DiffError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let err_msg = match self {
+            DiffError::InvalidPath(path) => format!("Invalid Path: {}", path),
+            DiffError::Cancelled => "Cancelled".to_string(),
+            DiffError::IoError(reason) => format!("IO Error: {}", reason),
+        };
+
+        serializer.serialize_str(err_msg.as_str())
+    }
+}
+
+impl From<io::Error> for DiffError {
+    fn from(err: io::Error) -> Self {
+        DiffError::IoError(err.to_string())
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CompareMode {
+    /// Text unless either file looks binary, is not valid UTF-8 or is larger
+    /// than `max_text_size`.
+    #[default]
+    Auto,
+    Text,
+    Binary,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct FileCompareOptions {
+    pub mode: CompareMode,
+    /// Unchanged lines shown around each text hunk.
+    pub context_lines: usize,
+    pub max_text_size: u64,
+}
+
+impl Default for FileCompareOptions {
+    fn default() -> Self {
+        Self {
+            mode: CompareMode::Auto,
+            context_lines: 3,
+            max_text_size: 16 * 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "mode", rename_all = "camelCase")]
+pub enum FileDiff {
+    Text(TextComparison),
+    Binary(BinaryComparison),
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextComparison {
+    /// Compares the raw bytes, so it can be false with no hunks when forced
+    /// text mode hides bytes that are not valid UTF-8.
+    pub identical: bool,
+    pub hunks: Vec<TextHunk>,
+}
+
+/// A run of changes with surrounding context. Line numbers start at 0.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextHunk {
+    pub old_start: usize,
+    pub old_len: usize,
+    pub new_start: usize,
+    pub new_len: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LineTag {
+    Equal,
+    Delete,
+    Insert,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffLine {
+    pub tag: LineTag,
+    pub old_line: Option<usize>,
+    pub new_line: Option<usize>,
+    /// The line without its line ending.
+    pub text: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BinaryComparison {
+    pub identical: bool,
+    pub left_size: u64,
+    pub right_size: u64,
+    /// Differing byte ranges, with the tail of the longer file as the last one.
+    pub ranges: Vec<ByteRange>,
+    /// Set when comparison stopped after `MAX_BYTE_RANGES` ranges.
+    pub truncated: bool,
+}
+
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ByteRange {
+    pub offset: u64,
+    pub len: u64,
+}
+
+/// How files present on both sides of a directory comparison are judged.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CompareStrategy {
+    Size,
+    /// Size and modification time, within a 2 second tolerance.
+    #[default]
+    SizeAndModified,
+    /// Size, then the contents of same-sized files.
+    Content,
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DirCompareOptions {
+    pub strategy: CompareStrategy,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DiffStatus {
+    /// Only on the right.
+    Added,
+    /// Only on the left.
+    Removed,
+    Modified,
+    Identical,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffSide {
+    pub kind: EntryKind,
+    pub size: u64,
+    pub modified: Option<u64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DirDiffEntry {
+    /// Path below both roots, with `/` separators.
+    pub relative: String,
+    pub status: DiffStatus,
+    pub left: Option<DiffSide>,
+    pub right: Option<DiffSide>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffFailure {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+#[derive(Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DirDiffSummary {
+    pub added: u64,
+    pub removed: u64,
+    pub modified: u64,
+    pub identical: u64,
+    pub failures: Vec<DiffFailure>,
+}
+
+/// Compares two files, or two directory trees.
+///
+/// Directory comparison streams one batch of entries per directory pair as soon
+/// as it is classified. Directories present on both sides are descended into
+/// but not reported themselves; one-sided directories are reported once,
+/// without their contents.
+pub struct DiffEngine {
+    control: Arc<OperationControl>,
+}
+
+impl DiffEngine {
+    pub fn new(control: Arc<OperationControl>) -> Self {
+        Self { control }
+    }
+
+    pub fn compare_files(
+        &self,
+        left: &Path,
+        right: &Path,
+        options: &FileCompareOptions,
+    ) -> Result<FileDiff, DiffError> {
+        for path in [left, right] {
+            if !path.is_file() {
+                return Err(DiffError::InvalidPath(path.display().to_string()));
+            }
+        }
+
+        let as_text = match options.mode {
+            CompareMode::Text => true,
+            CompareMode::Binary => false,
+            CompareMode::Auto => {
+                fs::metadata(left)?.len() <= options.max_text_size
+                    && fs::metadata(right)?.len() <= options.max_text_size
+                    && !looks_binary(left)?
+                    && !looks_binary(right)?
+            }
+        };
+
+        if as_text {
+            let left_bytes = fs::read(left)?;
+            let right_bytes = fs::read(right)?;
+            // Lossy decoding would turn different invalid bytes into the same text.
+            let decodes = std::str::from_utf8(&left_bytes).is_ok()
+                && std::str::from_utf8(&right_bytes).is_ok();
+            if decodes || options.mode == CompareMode::Text {
+                return Ok(FileDiff::Text(compare_text(
+                    &left_bytes,
+                    &right_bytes,
+                    options.context_lines,
+                )));
+            }
+        }
+        self.compare_binary(left, right).map(FileDiff::Binary)
+    }
+
+    fn compare_binary(&self, left: &Path, right: &Path) -> Result<BinaryComparison, DiffError> {
+        let mut left_file = File::open(left)?;
+        let mut right_file = File::open(right)?;
+        let left_size = left_file.metadata()?.len();
+        let right_size = right_file.metadata()?.len();
+
+        let mut ranges: Vec<ByteRange> = Vec::new();
+        let mut truncated = false;
+        let mut left_block = vec![0; BLOCK_SIZE];
+        let mut right_block = vec![0; BLOCK_SIZE];
+        let mut offset = 0;
+        let common = left_size.min(right_size);
+        while offset < common && !truncated {
+            if !self.control.checkpoint() {
+                return Err(DiffError::Cancelled);
+            }
+            let len = (common - offset).min(BLOCK_SIZE as u64) as usize;
+            left_file.read_exact(&mut left_block[..len])?;
+            right_file.read_exact(&mut right_block[..len])?;
+            if left_block[..len] == right_block[..len] {
+                offset += len as u64;
+                continue;
+            }
+
+            for (index, (a, b)) in left_block[..len]
+                .iter()
+                .zip(&right_block[..len])
+                .enumerate()
+            {
+                if a == b {
+                    continue;
+                }
+                let position = offset + index as u64;
+                if let Some(last) = ranges.last_mut() {
+                    if last.offset + last.len == position {
+                        last.len += 1;
+                        continue;
+                    }
+                }
+                if ranges.len() == MAX_BYTE_RANGES {
+                    truncated = true;
+                    break;
+                }
+                ranges.push(ByteRange {
+                    offset: position,
+                    len: 1,
+                });
+            }
+            offset += len as u64;
+        }
+
+        if left_size != right_size && !truncated {
+            let tail = ByteRange {
+                offset: common,
+                len: left_size.max(right_size) - common,
+            };
+            match ranges.last_mut() {
+                Some(last) if last.offset + last.len == common => last.len += tail.len,
+                _ => ranges.push(tail),
+            }
+        }
+
+        Ok(BinaryComparison {
+            identical: ranges.is_empty(),
+            left_size,
+            right_size,
+            ranges,
+            truncated,
+        })
+    }
+
+    pub fn compare_dirs<F>(
+        &self,
+        left: &Path,
+        right: &Path,
+        options: &DirCompareOptions,
+        on_entries: F,
+    ) -> Result<DirDiffSummary, DiffError>
+    where
+        F: Fn(Vec<DirDiffEntry>) + Sync,
+    {
+        for path in [left, right] {
+            if !path.is_dir() {
+                return Err(DiffError::InvalidPath(path.display().to_string()));
+            }
+        }
+
+        let walk = DirWalk {
+            engine: self,
+            strategy: options.strategy,
+            counts: [
+                AtomicU64::new(0),
+                AtomicU64::new(0),
+                AtomicU64::new(0),
+                AtomicU64::new(0),
+            ],
+            failures: Mutex::new(Vec::new()),
+            on_entries: &on_entries,
+        };
+        walk.visit(left, right, "");
+
+        if self.control.is_cancelled() {
+            return Err(DiffError::Cancelled);
+        }
+
+        let count = |status: DiffStatus| walk.counts[status as usize].load(Ordering::Relaxed);
+        Ok(DirDiffSummary {
+            added: count(DiffStatus::Added),
+            removed: count(DiffStatus::Removed),
+            modified: count(DiffStatus::Modified),
+            identical: count(DiffStatus::Identical),
+            failures: walk.failures.into_inner().unwrap(),
+        })
+    }
+
+    /// Whether two files of the same size have the same bytes.
+    fn same_content(&self, left: &Path, right: &Path) -> io::Result<bool> {
+        let mut left_file = File::open(left)?;
+        let mut right_file = File::open(right)?;
+        let mut left_block = vec![0; BLOCK_SIZE];
+        let mut right_block = vec![0; BLOCK_SIZE];
+        loop {
+            if !self.control.checkpoint() {
+                return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+            }
+            let read = read_full(&mut left_file, &mut left_block)?;
+            if read_full(&mut right_file, &mut right_block)? != read
+                || left_block[..read] != right_block[..read]
+            {
+                return Ok(false);
+            }
+            if read == 0 {
+                return Ok(true);
+            }
+        }
+    }
+}
+
+struct DirWalk<'a, F> {
+    engine: &'a DiffEngine,
+    strategy: CompareStrategy,
+    /// Indexed by `DiffStatus`.
+    counts: [AtomicU64; 4],
+    failures: Mutex<Vec<DiffFailure>>,
+    on_entries: &'a F,
+}
+
+impl<F> DirWalk<'_, F>
+where
+    F: Fn(Vec<DirDiffEntry>) + Sync,
+{
+    fn visit(&self, left: &Path, right: &Path, relative: &str) {
+        if !self.engine.control.checkpoint() {
+            return;
+        }
+
+        let mut names: BTreeMap<OsString, (Option<fs::Metadata>, Option<fs::Metadata>)> =
+            BTreeMap::new();
+        for (dir, is_left) in [(left, true), (right, false)] {
+            let entries = match fs::read_dir(dir) {
+                Ok(entries) => entries,
+                Err(err) => {
+                    self.fail(dir, err);
+                    return;
+                }
+            };
+            for entry in entries.flatten() {
+                // DirEntry::metadata does not traverse symlinks.
+                let metadata = match entry.metadata() {
+                    Ok(metadata) => metadata,
+                    Err(err) => {
+                        self.fail(&entry.path(), err);
+                        continue;
+                    }
+                };
+                let sides = names.entry(entry.file_name()).or_default();
+                if is_left {
+                    sides.0 = Some(metadata);
+                } else {
+                    sides.1 = Some(metadata);
+                }
+            }
+        }
+
+        let mut subdirs = Vec::new();
+        let mut pending = Vec::new();
+        for (name, sides) in names {
+            let child = join_relative(relative, &name);
+            match sides {
+                (Some(left_meta), Some(right_meta))
+                    if left_meta.is_dir() && right_meta.is_dir() =>
+                {
+                    subdirs.push((left.join(&name), right.join(&name), child));
+                }
+                (left_meta, right_meta) => {
+                    pending.push((name, child, left_meta, right_meta));
+                }
+            }
+        }
+
+        let entries: Vec<DirDiffEntry> = pending
+            .into_par_iter()
+            .filter_map(|(name, child, left_meta, right_meta)| {
+                let status = match (&left_meta, &right_meta) {
+                    (None, Some(_)) => DiffStatus::Added,
+                    (Some(_), None) => DiffStatus::Removed,
+                    (Some(left_meta), Some(right_meta)) => {
+                        match self.classify(
+                            &left.join(&name),
+                            left_meta,
+                            &right.join(&name),
+                            right_meta,
+                        ) {
+                            Ok(status) => status,
+                            Err(err) => {
+                                self.fail(&left.join(&name), err);
+                                return None;
+                            }
+                        }
+                    }
+                    (None, None) => return None,
+                };
+                self.counts[status as usize].fetch_add(1, Ordering::Relaxed);
+                Some(DirDiffEntry {
+                    relative: child,
+                    status,
+                    left: left_meta.as_ref().map(diff_side),
+                    right: right_meta.as_ref().map(diff_side),
+                })
+            })
+            .collect();
+        if !entries.is_empty() && !self.engine.control.is_cancelled() {
+            (self.on_entries)(entries);
+        }
+
+        subdirs
+            .par_iter()
+            .for_each(|(left, right, child)| self.visit(left, right, child));
+    }
+
+    fn classify(
+        &self,
+        left: &Path,
+        left_meta: &fs::Metadata,
+        right: &Path,
+        right_meta: &fs::Metadata,
+    ) -> io::Result<DiffStatus> {
+        let left_kind = EntryKind::of(left_meta.file_type());
+        let right_kind = EntryKind::of(right_meta.file_type());
+        let same = match (left_kind, right_kind) {
+            (EntryKind::Symlink, EntryKind::Symlink) => {
+                fs::read_link(left)? == fs::read_link(right)?
+            }
+            (EntryKind::File, EntryKind::File) => {
+                left_meta.len() == right_meta.len()
+                    && match self.strategy {
+                        CompareStrategy::Size => true,
+                        CompareStrategy::SizeAndModified => same_modified(left_meta, right_meta),
+                        CompareStrategy::Content => self.engine.same_content(left, right)?,
+                    }
+            }
+            _ => false,
+        };
+        Ok(if same {
+            DiffStatus::Identical
+        } else {
+            DiffStatus::Modified
+        })
+    }
+
+    fn fail(&self, path: &Path, err: io::Error) {
+        self.failures.lock().unwrap().push(DiffFailure {
+            path: path.to_path_buf(),
+            reason: err.to_string(),
+        });
+    }
+}
+
+fn compare_text(left: &[u8], right: &[u8], context_lines: usize) -> TextComparison {
+    if left == right {
+        return TextComparison {
+            identical: true,
+            hunks: Vec::new(),
+        };
+    }
+
+    let left = String::from_utf8_lossy(left);
+    let right = String::from_utf8_lossy(right);
+    let diff = TextDiff::configure()
+        .algorithm(Algorithm::Myers)
+        .timeout(TEXT_DIFF_TIMEOUT)
+        .diff_lines(left.as_ref(), right.as_ref());
+
+    let hunks: Vec<TextHunk> = diff
+        .grouped_ops(context_lines)
+        .into_iter()
+        .map(|ops| {
+            let first = ops.first().expect("grouped ops are never empty");
+            let last = ops.last().expect("grouped ops are never empty");
+            let lines = ops
+                .iter()
+                .flat_map(|op| diff.iter_changes(op))
+                .map(|change| DiffLine {
+                    tag: match change.tag() {
+                        ChangeTag::Equal => LineTag::Equal,
+                        ChangeTag::Delete => LineTag::Delete,
+                        ChangeTag::Insert => LineTag::Insert,
+                    },
+                    old_line: change.old_index(),
+                    new_line: change.new_index(),
+                    text: change.value().trim_end_matches(['\n', '\r']).to_string(),
+                })
+                .collect();
+            TextHunk {
+                old_start: first.old_range().start,
+                old_len: last.old_range().end - first.old_range().start,
+                new_start: first.new_range().start,
+                new_len: last.new_range().end - first.new_range().start,
+                lines,
+            }
+        })
+        .collect();
+
+    TextComparison {
+        identical: false,
+        hunks,
+    }
+}
+
+fn looks_binary(path: &Path) -> io::Result<bool> {
+    let mut head = Vec::with_capacity(SNIFF_SIZE);
+    File::open(path)?
+        .take(SNIFF_SIZE as u64)
+        .read_to_end(&mut head)?;
+    Ok(head.contains(&0))
+}
+
+/// Reads until `buffer` is full or the file ends.
+fn read_full(file: &mut File, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match file.read(&mut buffer[filled..])? {
+            0 => break,
+            read => filled += read,
+        }
+    }
+    Ok(filled)
+}
+
+fn same_modified(left: &fs::Metadata, right: &fs::Metadata) -> bool {
+    let left = left.modified().ok().and_then(unix_millis);
+    let right = right.modified().ok().and_then(unix_millis);
+    match (left, right) {
+        (Some(left), Some(right)) => left.abs_diff(right) <= MODIFIED_TOLERANCE_MS,
+        _ => false,
+    }
+}
+
+fn diff_side(metadata: &fs::Metadata) -> DiffSide {
+    DiffSide {
+        kind: EntryKind::of(metadata.file_type()),
+        size: metadata.len(),
+        modified: metadata.modified().ok().and_then(unix_millis),
+    }
+}
+
+fn join_relative(relative: &str, name: &OsString) -> String {
+    let name = name.to_string_lossy();
+    if relative.is_empty() {
+        name.into_owned()
+    } else {
+        format!("{}/{}", relative, name)
+    }
+}
+
+#[tauri::command]
+pub async fn compare_files(
+    str_left: String,
+    str_right: String,
+    options: Option<FileCompareOptions>,
+    operation_id: String,
+    registry: tauri::State<'_, OperationRegistry>,
+) -> Result<FileDiff, DiffError> {
+    let engine = DiffEngine::new(registry.register(&operation_id));
+    let options = options.unwrap_or_default();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        engine.compare_files(Path::new(&str_left), Path::new(&str_right), &options)
+    })
+    .await;
+
+    registry.finish(&operation_id);
+    result.map_err(|err| DiffError::IoError(err.to_string()))?
+}
+
+#[tauri::command]
+pub async fn compare_directories(
+    str_left: String,
+    str_right: String,
+    options: DirCompareOptions,
+    operation_id: String,
+    on_entries: Channel<Vec<DirDiffEntry>>,
+    registry: tauri::State<'_, OperationRegistry>,
+) -> Result<DirDiffSummary, DiffError> {
+    let engine = DiffEngine::new(registry.register(&operation_id));
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        engine.compare_dirs(
+            Path::new(&str_left),
+            Path::new(&str_right),
+            &options,
+            |entries| {
+                let _ = on_entries.send(entries);
+            },
+        )
+    })
+    .await;
+
+    registry.finish(&operation_id);
+    result.map_err(|err| DiffError::IoError(err.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::time::SystemTime;
+
+    use super::*;
+
+    fn engine() -> DiffEngine {
+        DiffEngine::new(Arc::default())
+    }
+
+    fn binary(left: &[u8], right: &[u8]) -> BinaryComparison {
+        let dir = tempfile::tempdir().unwrap();
+        let (left_path, right_path) = (dir.path().join("left"), dir.path().join("right"));
+        fs::write(&left_path, left).unwrap();
+        fs::write(&right_path, right).unwrap();
+        engine()
+            .compare_binary(&left_path, &right_path)
+            .ok()
+            .unwrap()
+    }
+
+    fn ranges(comparison: &BinaryComparison) -> Vec<(u64, u64)> {
+        comparison
+            .ranges
+            .iter()
+            .map(|range| (range.offset, range.len))
+            .collect()
+    }
+
+    #[test]
+    fn binary_merges_adjacent_bytes_across_blocks() {
+        let left = vec![0u8; BLOCK_SIZE * 2];
+        let mut right = left.clone();
+        for index in [10, 11, 12, 50, BLOCK_SIZE - 1, BLOCK_SIZE] {
+            right[index] = 1;
+        }
+        let comparison = binary(&left, &right);
+        assert!(!comparison.identical);
+        assert!(!comparison.truncated);
+        assert_eq!(
+            ranges(&comparison),
+            [(10, 3), (50, 1), (BLOCK_SIZE as u64 - 1, 2)]
+        );
+    }
+
+    #[test]
+    fn binary_reports_the_tail_of_the_longer_file() {
+        assert_eq!(ranges(&binary(b"abcdef", b"abcdefgh")), [(6, 2)]);
+        // A difference touching the end of the common part joins the tail.
+        assert_eq!(ranges(&binary(b"abcdeX", b"abcdefgh")), [(5, 3)]);
+        assert_eq!(ranges(&binary(b"abcdefgh", b"abXdef")), [(2, 1), (6, 2)]);
+        assert!(binary(b"same", b"same").identical);
+    }
+
+    #[test]
+    fn binary_stops_after_the_range_limit() {
+        let left = vec![0u8; (MAX_BYTE_RANGES + 10) * 2];
+        let right: Vec<u8> = (0..left.len()).map(|index| (index % 2) as u8).collect();
+        let mut longer = right.clone();
+        longer.push(0);
+        let comparison = binary(&left, &longer);
+        assert!(comparison.truncated);
+        assert_eq!(comparison.ranges.len(), MAX_BYTE_RANGES);
+        // No tail is added once the list is cut short.
+        assert_eq!(comparison.ranges.last().unwrap().len, 1);
+    }
+
+    #[test]
+    fn text_groups_changes_into_hunks_with_context() {
+        let left = b"a\nb\nc\nd\ne\nf\ng\nh\n";
+        let right = b"a\nB\nc\nd\ne\nf\ng\nH\n";
+        let comparison = compare_text(left, right, 1);
+        assert!(!comparison.identical);
+        assert_eq!(comparison.hunks.len(), 2);
+
+        let first = &comparison.hunks[0];
+        assert_eq!((first.old_start, first.old_len), (0, 3));
+        assert_eq!((first.new_start, first.new_len), (0, 3));
+        let tags: Vec<LineTag> = first.lines.iter().map(|line| line.tag).collect();
+        assert!(matches!(
+            tags.as_slice(),
+            [
+                LineTag::Equal,
+                LineTag::Delete,
+                LineTag::Insert,
+                LineTag::Equal
+            ]
+        ));
+        assert_eq!(first.lines[1].text, "b");
+        assert_eq!(first.lines[1].old_line, Some(1));
+        assert_eq!(first.lines[2].new_line, Some(1));
+
+        let second = &comparison.hunks[1];
+        assert_eq!((second.old_start, second.old_len), (6, 2));
+
+        assert!(compare_text(left, left, 3).identical);
+    }
+
+    #[test]
+    fn invalid_utf8_is_compared_as_bytes() {
+        // Latin-1 "café" and "cafè" both decode to "caf\u{fffd}".
+        assert!(!compare_text(b"caf\xe9", b"caf\xe8", 3).identical);
+
+        let dir = tempfile::tempdir().unwrap();
+        let (left, right) = (dir.path().join("left"), dir.path().join("right"));
+        fs::write(&left, b"caf\xe9").unwrap();
+        fs::write(&right, b"caf\xe8").unwrap();
+        let diff = engine()
+            .compare_files(&left, &right, &FileCompareOptions::default())
+            .ok()
+            .unwrap();
+        assert!(matches!(
+            diff,
+            FileDiff::Binary(BinaryComparison {
+                identical: false,
+                ..
+            })
+        ));
+    }
+
+    fn write(root: &Path, name: &str, contents: &str, modified: SystemTime) {
+        let path = root.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, contents).unwrap();
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+
+    fn compare_dirs(strategy: CompareStrategy) -> HashMap<String, DiffStatus> {
+        let left = tempfile::tempdir().unwrap();
+        let right = tempfile::tempdir().unwrap();
+        let then = SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        let later = then + Duration::from_secs(60);
+
+        write(left.path(), "left-only", "x", then);
+        write(right.path(), "right-only", "x", then);
+        write(left.path(), "same", "same", then);
+        write(right.path(), "same", "same", then);
+        write(left.path(), "sub/same-size", "abcd", then);
+        write(right.path(), "sub/same-size", "abXd", then);
+        write(left.path(), "resized", "abc", then);
+        write(right.path(), "resized", "abcd", then);
+        write(left.path(), "touched", "same", then);
+        write(right.path(), "touched", "same", later);
+        fs::create_dir(left.path().join("left-dir")).unwrap();
+
+        let entries = Mutex::new(HashMap::new());
+        let summary = engine()
+            .compare_dirs(
+                left.path(),
+                right.path(),
+                &DirCompareOptions { strategy },
+                |batch| {
+                    let mut entries = entries.lock().unwrap();
+                    for entry in batch {
+                        entries.insert(entry.relative, entry.status);
+                    }
+                },
+            )
+            .ok()
+            .unwrap();
+        assert!(summary.failures.is_empty());
+        assert_eq!((summary.added, summary.removed), (1, 2));
+        entries.into_inner().unwrap()
+    }
+
+    fn assert_statuses(entries: &HashMap<String, DiffStatus>, expected: &[(&str, DiffStatus)]) {
+        assert_eq!(entries.len(), expected.len());
+        for (relative, status) in expected {
+            assert!(entries[*relative] == *status, "{}", relative);
+        }
+    }
+
+    #[test]
+    fn dirs_by_size() {
+        assert_statuses(
+            &compare_dirs(CompareStrategy::Size),
+            &[
+                ("left-only", DiffStatus::Removed),
+                ("left-dir", DiffStatus::Removed),
+                ("right-only", DiffStatus::Added),
+                ("same", DiffStatus::Identical),
+                ("sub/same-size", DiffStatus::Identical),
+                ("resized", DiffStatus::Modified),
+                ("touched", DiffStatus::Identical),
+            ],
+        );
+    }
+
+    #[test]
+    fn dirs_by_size_and_modified() {
+        assert_statuses(
+            &compare_dirs(CompareStrategy::SizeAndModified),
+            &[
+                ("left-only", DiffStatus::Removed),
+                ("left-dir", DiffStatus::Removed),
+                ("right-only", DiffStatus::Added),
+                ("same", DiffStatus::Identical),
+                ("sub/same-size", DiffStatus::Identical),
+                ("resized", DiffStatus::Modified),
+                ("touched", DiffStatus::Modified),
+            ],
+        );
+    }
+
+    #[test]
+    fn dirs_by_content() {
+        assert_statuses(
+            &compare_dirs(CompareStrategy::Content),
+            &[
+                ("left-only", DiffStatus::Removed),
+                ("left-dir", DiffStatus::Removed),
+                ("right-only", DiffStatus::Added),
+                ("same", DiffStatus::Identical),
+                ("sub/same-size", DiffStatus::Modified),
+                ("resized", DiffStatus::Modified),
+                ("touched", DiffStatus::Identical),
+            ],
+        );
+    }
+}
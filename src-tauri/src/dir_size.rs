@@ -0,0 +1,397 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rayon::prelude::*;
+use serde::Serialize;
+use tauri::ipc::Channel;
+
+use crate::operations::{OperationControl, OperationRegistry, ProgressThrottle};
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+pub enum DirSizeError {
+    InvalidPath(String),
+    Cancelled,
+    IoError(String),
+}
+
+impl serde::Serialize for DirSizeError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let err_msg = match self {
+            DirSizeError::InvalidPath(path) => format!("Invalid Path: {}", path),
+            DirSizeError::Cancelled => "Cancelled".to_string(),
+            DirSizeError::IoError(reason) => format!("IO Error: {}", reason),
+        };
+
+        serializer.serialize_str(err_msg.as_str())
+    }
+}
+
+impl From<std::io::Error> for DirSizeError {
+    fn from(err: std::io::Error) -> Self {
+        DirSizeError::IoError(err.to_string())
+    }
+}
+
+/// Totals for a directory tree. Also sent as partial updates while the walk is running.
+#[derive(Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DirSize {
+    pub path: PathBuf,
+    pub bytes: u64,
+    /// Space actually allocated on disk, which differs from `bytes` for
+    /// compressed, sparse and cloud placeholder files and by block rounding.
+    pub allocated_bytes: u64,
+    pub files: u64,
+    pub dirs: u64,
+    /// Entries that could not be read, e.g. because of permissions.
+    pub skipped: u64,
+    pub finished: bool,
+}
+
+#[derive(Default)]
+struct Counters {
+    bytes: AtomicU64,
+    allocated_bytes: AtomicU64,
+    files: AtomicU64,
+    dirs: AtomicU64,
+    skipped: AtomicU64,
+}
+
+/// Computes recursive directory sizes in parallel.
+///
+/// Symlinks are not followed and files with several hard links are counted once.
+pub struct DirSizeCalculator {
+    control: Arc<OperationControl>,
+}
+
+impl DirSizeCalculator {
+    pub fn new(control: Arc<OperationControl>) -> Self {
+        Self { control }
+    }
+
+    /// Walks `root` and returns its totals. `on_progress` receives running totals
+    /// at most every 100ms.
+    pub fn calculate<F>(&self, root: &Path, on_progress: F) -> Result<DirSize, DirSizeError>
+    where
+        F: Fn(DirSize) + Sync,
+    {
+        if !root.is_dir() {
+            return Err(DirSizeError::InvalidPath(root.display().to_string()));
+        }
+
+        let walk = Walk {
+            root,
+            control: &self.control,
+            counters: Counters::default(),
+            seen_links: Mutex::new(HashSet::new()),
+            throttle: ProgressThrottle::new(PROGRESS_INTERVAL),
+            on_progress: &on_progress,
+        };
+
+        // Fail early on an unreadable root instead of reporting it as skipped.
+        fs::read_dir(root)?;
+        walk.visit(root);
+
+        if self.control.is_cancelled() {
+            return Err(DirSizeError::Cancelled);
+        }
+
+        Ok(walk.snapshot(true))
+    }
+}
+
+struct Walk<'a, F> {
+    root: &'a Path,
+    control: &'a OperationControl,
+    counters: Counters,
+    seen_links: Mutex<HashSet<(u64, u64)>>,
+    throttle: ProgressThrottle,
+    on_progress: &'a F,
+}
+
+impl<F> Walk<'_, F>
+where
+    F: Fn(DirSize) + Sync,
+{
+    fn visit(&self, dir: &Path) {
+        if !self.control.checkpoint() {
+            return;
+        }
+
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => {
+                self.counters.skipped.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+        };
+
+        let links = DirLinks::read(dir);
+        let mut subdirs = Vec::new();
+        for entry in entries {
+            let Ok(entry) = entry else {
+                self.counters.skipped.fetch_add(1, Ordering::Relaxed);
+                continue;
+            };
+            // DirEntry::metadata does not traverse symlinks.
+            let Ok(metadata) = entry.metadata() else {
+                self.counters.skipped.fetch_add(1, Ordering::Relaxed);
+                continue;
+            };
+
+            if metadata.is_dir() {
+                self.counters.dirs.fetch_add(1, Ordering::Relaxed);
+                subdirs.push(entry.path());
+            } else {
+                if self.is_repeated_link(links.id(&entry, &metadata)) {
+                    continue;
+                }
+                let path = entry.path();
+                self.counters.files.fetch_add(1, Ordering::Relaxed);
+                self.counters
+                    .bytes
+                    .fetch_add(metadata.len(), Ordering::Relaxed);
+                self.counters
+                    .allocated_bytes
+                    .fetch_add(allocated_size(&path, &metadata), Ordering::Relaxed);
+            }
+        }
+
+        self.report();
+        subdirs.par_iter().for_each(|subdir| self.visit(subdir));
+    }
+
+    /// Whether a file with this identity was already counted. Files without
+    /// one cannot be hard links.
+    fn is_repeated_link(&self, id: Option<(u64, u64)>) -> bool {
+        id.is_some_and(|id| !self.seen_links.lock().unwrap().insert(id))
+    }
+
+    fn report(&self) {
+        if self.throttle.ready() {
+            (self.on_progress)(self.snapshot(false));
+        }
+    }
+
+    fn snapshot(&self, finished: bool) -> DirSize {
+        DirSize {
+            path: self.root.to_path_buf(),
+            bytes: self.counters.bytes.load(Ordering::Relaxed),
+            allocated_bytes: self.counters.allocated_bytes.load(Ordering::Relaxed),
+            files: self.counters.files.load(Ordering::Relaxed),
+            dirs: self.counters.dirs.load(Ordering::Relaxed),
+            skipped: self.counters.skipped.load(Ordering::Relaxed),
+            finished,
+        }
+    }
+}
+
+/// Identifies the files of one directory that may be hard links, as a pair of
+/// device and file number.
+struct DirLinks {
+    /// Std does not expose link counts on Windows, so every file is identified
+    /// by volume serial number and file id, read in one pass over the directory
+    /// rather than by opening each file.
+    #[cfg(windows)]
+    ids: std::collections::HashMap<std::ffi::OsString, (u64, u64)>,
+}
+
+impl DirLinks {
+    #[cfg(not(windows))]
+    fn read(_dir: &Path) -> Self {
+        Self {}
+    }
+
+    #[cfg(unix)]
+    fn id(&self, _entry: &fs::DirEntry, metadata: &fs::Metadata) -> Option<(u64, u64)> {
+        use std::os::unix::fs::MetadataExt;
+
+        (metadata.nlink() > 1).then(|| (metadata.dev(), metadata.ino()))
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn id(&self, _entry: &fs::DirEntry, _metadata: &fs::Metadata) -> Option<(u64, u64)> {
+        None
+    }
+
+    #[cfg(windows)]
+    fn read(dir: &Path) -> Self {
+        use std::ffi::OsString;
+        use std::os::windows::ffi::OsStringExt;
+        use std::os::windows::fs::OpenOptionsExt;
+        use std::os::windows::io::AsRawHandle;
+
+        use windows_sys::Win32::Storage::FileSystem::{
+            FileIdBothDirectoryInfo, GetFileInformationByHandle, GetFileInformationByHandleEx,
+            BY_HANDLE_FILE_INFORMATION, FILE_FLAG_BACKUP_SEMANTICS, FILE_ID_BOTH_DIR_INFO,
+            FILE_LIST_DIRECTORY,
+        };
+
+        let mut ids = std::collections::HashMap::new();
+        let Ok(directory) = fs::OpenOptions::new()
+            .access_mode(FILE_LIST_DIRECTORY)
+            .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+            .open(dir)
+        else {
+            return Self { ids };
+        };
+        let handle = directory.as_raw_handle();
+
+        // SAFETY: BY_HANDLE_FILE_INFORMATION is plain data, so all zeros is valid.
+        let mut info: BY_HANDLE_FILE_INFORMATION = unsafe { std::mem::zeroed() };
+        // SAFETY: the handle stays open for the call and `info` is writable.
+        if unsafe { GetFileInformationByHandle(handle, &mut info) } == 0 {
+            return Self { ids };
+        }
+        let volume = info.dwVolumeSerialNumber as u64;
+
+        // u64 elements keep the records 8-byte aligned, as the API expects.
+        let mut buffer = vec![0u64; 8 * 1024];
+        let buffer_size = (buffer.len() * std::mem::size_of::<u64>()) as u32;
+        // Each call continues the listing; it fails with ERROR_NO_MORE_FILES at the end.
+        // SAFETY: the buffer is writable for `buffer_size` bytes.
+        while unsafe {
+            GetFileInformationByHandleEx(
+                handle,
+                FileIdBothDirectoryInfo,
+                buffer.as_mut_ptr().cast(),
+                buffer_size,
+            )
+        } != 0
+        {
+            let mut record = buffer.as_ptr().cast::<FILE_ID_BOTH_DIR_INFO>();
+            loop {
+                // SAFETY: the call filled the buffer with a chain of records, each
+                // followed by its name, linked by NextEntryOffset.
+                let (next, file_id, name) = unsafe {
+                    let name = std::slice::from_raw_parts(
+                        std::ptr::addr_of!((*record).FileName).cast::<u16>(),
+                        (*record).FileNameLength as usize / 2,
+                    );
+                    ((*record).NextEntryOffset, (*record).FileId, name)
+                };
+                // File systems without stable file ids, such as FAT, report 0.
+                if file_id != 0 {
+                    ids.insert(OsString::from_wide(name), (volume, file_id as u64));
+                }
+                if next == 0 {
+                    break;
+                }
+                // SAFETY: a non-zero offset points at the next record in the buffer.
+                record = unsafe { record.cast::<u8>().add(next as usize).cast() };
+            }
+        }
+        Self { ids }
+    }
+
+    #[cfg(windows)]
+    fn id(&self, entry: &fs::DirEntry, _metadata: &fs::Metadata) -> Option<(u64, u64)> {
+        self.ids.get(&entry.file_name()).copied()
+    }
+}
+
+/// Bytes allocated on disk for a file: `st_blocks` on Unix and
+/// `GetCompressedFileSizeW` on Windows. Falls back to the logical size elsewhere.
+#[cfg(unix)]
+pub fn allocated_size(_path: &Path, metadata: &fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+
+    // st_blocks is always in 512-byte units, whatever the file system block size.
+    metadata.blocks() * 512
+}
+
+#[cfg(windows)]
+pub fn allocated_size(path: &Path, metadata: &fs::Metadata) -> u64 {
+    use std::os::windows::ffi::OsStrExt;
+
+    use windows_sys::Win32::Foundation::{GetLastError, NO_ERROR};
+    use windows_sys::Win32::Storage::FileSystem::{GetCompressedFileSizeW, INVALID_FILE_SIZE};
+
+    let wide_path: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut high = 0u32;
+    // SAFETY: `wide_path` is NUL-terminated and outlives the call.
+    let low = unsafe { GetCompressedFileSizeW(wide_path.as_ptr(), &mut high) };
+    // INVALID_FILE_SIZE is also a valid low word, so only GetLastError tells them apart.
+    if low == INVALID_FILE_SIZE && unsafe { GetLastError() } != NO_ERROR {
+        return metadata.len();
+    }
+    ((high as u64) << 32) | low as u64
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn allocated_size(_path: &Path, metadata: &fs::Metadata) -> u64 {
+    metadata.len()
+}
+
+#[tauri::command]
+pub async fn calculate_dir_size(
+    str_path: String,
+    operation_id: String,
+    on_progress: Channel<DirSize>,
+    registry: tauri::State<'_, OperationRegistry>,
+) -> Result<DirSize, DirSizeError> {
+    let calculator = DirSizeCalculator::new(registry.register(&operation_id));
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        calculator.calculate(Path::new(&str_path), |partial| {
+            let _ = on_progress.send(partial);
+        })
+    })
+    .await;
+
+    registry.finish(&operation_id);
+    result.map_err(|err| DirSizeError::IoError(err.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use super::*;
+
+    fn calculate(root: &Path) -> DirSize {
+        DirSizeCalculator::new(Arc::default())
+            .calculate(root, |_| {})
+            .ok()
+            .unwrap()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hard_links_are_counted_once() {
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir(root.path().join("sub")).unwrap();
+        fs::write(root.path().join("file"), vec![1; 1000]).unwrap();
+        fs::hard_link(root.path().join("file"), root.path().join("sub/link")).unwrap();
+        fs::write(root.path().join("other"), vec![1; 24]).unwrap();
+
+        let size = calculate(root.path());
+        assert_eq!(size.files, 2);
+        assert_eq!(size.dirs, 1);
+        assert_eq!(size.bytes, 1024);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn sparse_files_allocate_less_than_their_length() {
+        let root = tempfile::tempdir().unwrap();
+        let sparse = root.path().join("sparse");
+        File::create(&sparse)
+            .unwrap()
+            .set_len(16 * 1024 * 1024)
+            .unwrap();
+
+        let metadata = fs::metadata(&sparse).unwrap();
+        assert!(allocated_size(&sparse, &metadata) < metadata.len());
+
+        let size = calculate(root.path());
+        assert!(size.allocated_bytes < size.bytes);
+    }
+}
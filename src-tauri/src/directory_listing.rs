@@ -0,0 +1,421 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use tauri::ipc::Channel;
+
+use crate::metadata::{entry_metadata, EntryKind, EntryMetadata};
+use crate::sorting::{NameCollator, SortOptions};
+
+const DEFAULT_BATCH_SIZE: usize = 500;
+
+pub enum ListError {
+    InvalidPath(String),
+    IoError(String),
+}
+
+impl serde::Serialize for ListError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let err_msg = match self {
+            ListError::InvalidPath(path) => format!("Invalid Path: {}", path),
+            ListError::IoError(reason) => format!("IO Error: {}", reason),
+        };
+
+        serializer.serialize_str(err_msg.as_str())
+    }
+}
+
+impl From<std::io::Error> for ListError {
+    fn from(err: std::io::Error) -> Self {
+        ListError::IoError(err.to_string())
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ListOptions {
+    pub sort: SortOptions,
+    pub include_hidden: bool,
+    pub include_metadata: bool,
+    pub batch_size: usize,
+}
+
+impl Default for ListOptions {
+    fn default() -> Self {
+        Self {
+            sort: SortOptions::default(),
+            include_hidden: false,
+            include_metadata: true,
+            batch_size: DEFAULT_BATCH_SIZE,
+        }
+    }
+}
+
+/// Icon category derived from the entry kind and file extension.
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IconType {
+    Folder,
+    Image,
+    Video,
+    Audio,
+    Archive,
+    Document,
+    Code,
+    Executable,
+    Text,
+    Other,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListEntry {
+    pub name: String,
+    pub path: PathBuf,
+    pub kind: EntryKind,
+    /// For symlinks, whether the link points at a directory.
+    pub is_dir: bool,
+    pub hidden: bool,
+    pub icon: IconType,
+    pub metadata: Option<EntryMetadata>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListBatch {
+    /// Position of the first entry of this batch in the sorted listing.
+    pub offset: usize,
+    pub total: usize,
+    pub entries: Vec<ListEntry>,
+}
+
+/// Lists a single directory in sorted batches.
+///
+/// Names and entry types come from one `read_dir` pass, which is cheap even for
+/// very large folders. Metadata is only read batch by batch, in parallel, right
+/// before each batch goes out. An empty directory produces no batches.
+pub struct DirectoryLister {
+    options: ListOptions,
+}
+
+impl DirectoryLister {
+    pub fn new(options: ListOptions) -> Self {
+        Self { options }
+    }
+
+    /// Sends the listing to `on_batch` and returns the number of entries.
+    pub fn list<F>(&self, dir: &Path, mut on_batch: F) -> Result<usize, ListError>
+    where
+        F: FnMut(ListBatch) -> bool,
+    {
+        if !dir.is_dir() {
+            return Err(ListError::InvalidPath(dir.display().to_string()));
+        }
+
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let path = entry.path();
+            let file_type = entry.file_type()?;
+            let hidden = is_hidden(&name, &entry);
+            if hidden && !self.options.include_hidden {
+                continue;
+            }
+
+            let kind = EntryKind::of(file_type);
+            let is_dir = match kind {
+                EntryKind::Symlink => path.is_dir(),
+                _ => file_type.is_dir(),
+            };
+            entries.push(ListEntry {
+                icon: icon_type(&name, is_dir),
+                name,
+                path,
+                kind,
+                is_dir,
+                hidden,
+                metadata: None,
+            });
+        }
+
+        let collator = NameCollator::new(&self.options.sort);
+        let compare = |a: &ListEntry, b: &ListEntry| {
+            collator.compare_entries((&a.name, a.is_dir), (&b.name, b.is_dir))
+        };
+
+        // Collation is the slow part of a huge listing, so pick out and send the
+        // first batch before sorting everything else.
+        let total = entries.len();
+        if total == 0 {
+            return Ok(0);
+        }
+        let batch_size = self.options.batch_size.max(1);
+        let first_len = batch_size.min(total);
+        if first_len < total {
+            entries.select_nth_unstable_by(first_len - 1, compare);
+        }
+        let mut rest = entries.split_off(first_len);
+        entries.sort_by(compare);
+        if !self.send_batch(entries, 0, total, &mut on_batch) {
+            return Ok(total);
+        }
+
+        rest.sort_by(compare);
+        let mut offset = first_len;
+        let mut rest = rest.into_iter().peekable();
+        while rest.peek().is_some() {
+            let batch: Vec<ListEntry> = rest.by_ref().take(batch_size).collect();
+            let len = batch.len();
+            if !self.send_batch(batch, offset, total, &mut on_batch) {
+                break;
+            }
+            offset += len;
+        }
+
+        Ok(total)
+    }
+
+    fn send_batch<F>(
+        &self,
+        mut entries: Vec<ListEntry>,
+        offset: usize,
+        total: usize,
+        on_batch: &mut F,
+    ) -> bool
+    where
+        F: FnMut(ListBatch) -> bool,
+    {
+        if self.options.include_metadata {
+            entries
+                .par_iter_mut()
+                .for_each(|entry| entry.metadata = entry_metadata(&entry.path));
+        }
+        on_batch(ListBatch {
+            offset,
+            total,
+            entries,
+        })
+    }
+}
+
+#[cfg(windows)]
+fn is_hidden(name: &str, entry: &fs::DirEntry) -> bool {
+    use std::os::windows::fs::MetadataExt;
+
+    const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+    // On Windows the attributes come from the directory scan itself.
+    name.starts_with('.')
+        || entry
+            .metadata()
+            .is_ok_and(|metadata| metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0)
+}
+
+#[cfg(not(windows))]
+fn is_hidden(name: &str, _entry: &fs::DirEntry) -> bool {
+    name.starts_with('.')
+}
+
+fn icon_type(name: &str, is_dir: bool) -> IconType {
+    if is_dir {
+        return IconType::Folder;
+    }
+    let extension = match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => extension.to_ascii_lowercase(),
+        _ => return IconType::Other,
+    };
+
+    match extension.as_str() {
+        "png" | "jpg" | "jpeg" | "gif" | "bmp" | "webp" | "svg" | "ico" | "tif" | "tiff"
+        | "heic" | "avif" | "raw" | "cr2" | "nef" | "dng" => IconType::Image,
+        "mp4" | "mkv" | "mov" | "avi" | "webm" | "wmv" | "flv" | "m4v" | "mpg" | "mpeg" => {
+            IconType::Video
+        }
+        "mp3" | "wav" | "flac" | "ogg" | "m4a" | "aac" | "wma" | "opus" | "aiff" => IconType::Audio,
+        "zip" | "tar" | "gz" | "tgz" | "bz2" | "xz" | "zst" | "7z" | "rar" | "iso" | "dmg"
+        | "cab" => IconType::Archive,
+        "pdf" | "doc" | "docx" | "odt" | "rtf" | "xls" | "xlsx" | "ods" | "csv" | "ppt"
+        | "pptx" | "odp" | "epub" => IconType::Document,
+        "rs" | "js" | "ts" | "jsx" | "tsx" | "py" | "java" | "c" | "h" | "cpp" | "hpp" | "cs"
+        | "go" | "rb" | "php" | "swift" | "kt" | "html" | "css" | "scss" | "json" | "toml"
+        | "yaml" | "yml" | "xml" | "sh" | "ps1" | "sql" => IconType::Code,
+        "exe" | "msi" | "bat" | "cmd" | "app" | "apk" | "deb" | "rpm" | "appimage" => {
+            IconType::Executable
+        }
+        "txt" | "md" | "log" | "ini" | "cfg" | "conf" => IconType::Text,
+        _ => IconType::Other,
+    }
+}
+
+#[tauri::command]
+pub async fn list_directory(
+    str_path: String,
+    options: Option<ListOptions>,
+    on_batch: Channel<ListBatch>,
+) -> Result<usize, ListError> {
+    let lister = DirectoryLister::new(options.unwrap_or_default());
+    tauri::async_runtime::spawn_blocking(move || {
+        lister.list(Path::new(&str_path), |batch| on_batch.send(batch).is_ok())
+    })
+    .await
+    .map_err(|err| ListError::IoError(err.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(batch_size: usize) -> ListOptions {
+        ListOptions {
+            include_metadata: false,
+            batch_size,
+            ..ListOptions::default()
+        }
+    }
+
+    fn list_batches(dir: &Path, options: ListOptions) -> Vec<ListBatch> {
+        let mut batches = Vec::new();
+        DirectoryLister::new(options)
+            .list(dir, |batch| {
+                batches.push(batch);
+                true
+            })
+            .ok()
+            .unwrap();
+        batches
+    }
+
+    fn names(batches: &[ListBatch]) -> Vec<String> {
+        batches
+            .iter()
+            .flat_map(|batch| batch.entries.iter().map(|entry| entry.name.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn batches_stay_sorted_across_the_first_batch_split() {
+        let dir = tempfile::tempdir().unwrap();
+        for index in (1..=23).rev() {
+            fs::write(dir.path().join(format!("file{}.txt", index)), b"").unwrap();
+        }
+        fs::create_dir(dir.path().join("zeta")).unwrap();
+        fs::create_dir(dir.path().join("alpha")).unwrap();
+
+        let batches = list_batches(dir.path(), options(4));
+
+        let mut expected = vec!["alpha".to_string(), "zeta".to_string()];
+        expected.extend((1..=23).map(|index| format!("file{}.txt", index)));
+        assert!(names(&batches) == expected);
+        assert!(batches.len() == 7);
+        let mut offset = 0;
+        for batch in &batches {
+            assert!(batch.offset == offset);
+            assert!(batch.total == 25);
+            assert!(batch.entries.len() <= 4);
+            offset += batch.entries.len();
+        }
+    }
+
+    #[test]
+    fn listing_stops_when_the_receiver_goes_away() {
+        let dir = tempfile::tempdir().unwrap();
+        for index in 0..10 {
+            fs::write(dir.path().join(format!("file{}", index)), b"").unwrap();
+        }
+
+        let mut calls = 0;
+        let total = DirectoryLister::new(options(3))
+            .list(dir.path(), |_| {
+                calls += 1;
+                calls < 2
+            })
+            .ok()
+            .unwrap();
+
+        assert!(total == 10);
+        assert!(calls == 2);
+    }
+
+    #[test]
+    fn empty_directory_sends_no_batches() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert!(list_batches(dir.path(), options(4)).is_empty());
+    }
+
+    #[test]
+    fn hidden_entries_are_listed_only_on_request() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(".hidden"), b"").unwrap();
+        fs::write(dir.path().join("visible"), b"").unwrap();
+
+        let batches = list_batches(dir.path(), options(10));
+        assert!(names(&batches) == ["visible"]);
+        assert!(batches[0].total == 1);
+
+        let batches = list_batches(
+            dir.path(),
+            ListOptions {
+                include_hidden: true,
+                ..options(10)
+            },
+        );
+        let entries = &batches[0].entries;
+        assert!(names(&batches) == [".hidden", "visible"]);
+        assert!(entries[0].hidden);
+        assert!(!entries[1].hidden);
+    }
+
+    #[test]
+    fn metadata_is_read_only_when_requested() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("file"), b"data").unwrap();
+
+        let batches = list_batches(dir.path(), options(10));
+        assert!(batches[0].entries[0].metadata.is_none());
+
+        let batches = list_batches(
+            dir.path(),
+            ListOptions {
+                include_metadata: true,
+                ..options(10)
+            },
+        );
+        assert!(batches[0].entries[0].metadata.is_some());
+    }
+
+    #[test]
+    fn missing_directory_is_an_invalid_path() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let result = DirectoryLister::new(options(10)).list(&dir.path().join("missing"), |_| true);
+
+        assert!(matches!(result, Err(ListError::InvalidPath(_))));
+    }
+
+    #[test]
+    fn icon_type_follows_kind_and_extension() {
+        assert!(matches!(icon_type("photos.png", true), IconType::Folder));
+        assert!(matches!(icon_type("Photo.JPG", false), IconType::Image));
+        assert!(matches!(icon_type("clip.mkv", false), IconType::Video));
+        assert!(matches!(icon_type("song.flac", false), IconType::Audio));
+        assert!(matches!(
+            icon_type("backup.tar.gz", false),
+            IconType::Archive
+        ));
+        assert!(matches!(icon_type("report.pdf", false), IconType::Document));
+        assert!(matches!(icon_type("main.rs", false), IconType::Code));
+        assert!(matches!(
+            icon_type("setup.exe", false),
+            IconType::Executable
+        ));
+        assert!(matches!(icon_type("notes.md", false), IconType::Text));
+        assert!(matches!(icon_type(".bashrc", false), IconType::Other));
+        assert!(matches!(icon_type("Makefile", false), IconType::Other));
+        assert!(matches!(icon_type("data.unknown", false), IconType::Other));
+    }
+}
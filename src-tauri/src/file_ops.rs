@@ -0,0 +1,847 @@
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::ipc::Channel;
+
+use crate::operations::{OperationControl, OperationRegistry, ProgressThrottle};
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+const BUFFER_SIZE: usize = 1024 * 1024;
+
+pub enum CopyError {
+    InvalidPath(String),
+    IntoItself(String),
+    Conflict(String),
+    Cancelled,
+    IoError(String),
+}
+
+impl serde::Serialize for CopyError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let err_msg = match self {
+            CopyError::InvalidPath(path) => format!("Invalid Path: {}", path),
+            CopyError::IntoItself(path) => format!("Cannot copy into itself: {}", path),
+            CopyError::Conflict(path) => format!("Already Exists: {}", path),
+            CopyError::Cancelled => "Cancelled".to_string(),
+            CopyError::IoError(reason) => format!("IO Error: {}", reason),
+        };
+
+        serializer.serialize_str(err_msg.as_str())
+    }
+}
+
+impl From<io::Error> for CopyError {
+    fn from(err: io::Error) -> Self {
+        CopyError::IoError(err.to_string())
+    }
+}
+
+/// What to do when a file already exists at the destination, or when two
+/// sources in one batch would be written to the same place.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OverwritePolicy {
+    /// Replace existing files. A file never replaces a directory or the
+    /// reverse, and two sources never overwrite each other: those fail with
+    /// `Conflict`.
+    Overwrite,
+    #[default]
+    Skip,
+    /// Keep both, giving the new file a `name (2).ext` style name.
+    Rename,
+    /// Like `Overwrite`, but skip files whose target is at least as recent.
+    OverwriteIfNewer,
+    /// Abort the whole operation before anything is written.
+    Fail,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum TransferMode {
+    #[default]
+    Copy,
+    Move,
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct CopyOptions {
+    pub overwrite: OverwritePolicy,
+    /// Compare SHA-256 digests of source and copy after each file is written.
+    pub verify: bool,
+    #[serde(skip)]
+    pub mode: TransferMode,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CopyProgress {
+    pub current_file: PathBuf,
+    pub current_file_bytes: u64,
+    pub current_file_total: u64,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    pub files_done: u64,
+    pub files_total: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CopyFailure {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+#[derive(Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CopyReport {
+    /// Files written, plus top-level items moved with a single rename.
+    pub files_copied: u64,
+    pub bytes_copied: u64,
+    /// Destination paths skipped because of the overwrite policy.
+    pub skipped: Vec<PathBuf>,
+    /// Destination paths written under a new name because of `OverwritePolicy::Rename`.
+    pub renamed: Vec<PathBuf>,
+    pub failed: Vec<CopyFailure>,
+    /// Set when the operation was cancelled part way; the rest of the report
+    /// covers what was done before that.
+    pub cancelled: bool,
+}
+
+enum JobKind {
+    File,
+    Symlink,
+}
+
+struct FileJob {
+    source: PathBuf,
+    target: PathBuf,
+    size: u64,
+    kind: JobKind,
+}
+
+/// A top-level move tried as a single rename, with the copy plan to fall back
+/// to when the source and target are on different volumes.
+struct PendingRename {
+    source: PathBuf,
+    target: PathBuf,
+    fallback: Plan,
+}
+
+#[derive(Default)]
+struct Plan {
+    /// Top-level items to move with a single rename once planning succeeded.
+    renames: Vec<PendingRename>,
+    dirs: Vec<PathBuf>,
+    jobs: Vec<FileJob>,
+    /// Source directories to remove after a move, parents before children.
+    source_dirs: Vec<PathBuf>,
+    /// Targets this batch writes to, files and directories alike.
+    claimed: HashSet<PathBuf>,
+    /// Directories this batch creates, which later sources may merge into.
+    planned_dirs: HashSet<PathBuf>,
+    report: CopyReport,
+}
+
+impl Plan {
+    fn absorb(&mut self, other: Plan) {
+        self.dirs.extend(other.dirs);
+        self.jobs.extend(other.jobs);
+        self.source_dirs.extend(other.source_dirs);
+        self.report.skipped.extend(other.report.skipped);
+        self.report.renamed.extend(other.report.renamed);
+    }
+}
+
+/// Copies or moves files and directory trees into a destination directory.
+///
+/// The plan (directories to create, conflicts to resolve) is built for every
+/// source before anything is written, then files are copied in parallel. Each
+/// file is written to a temporary name next to its target and renamed into
+/// place once complete, so a cancelled or failed copy never leaves a truncated
+/// file behind. Moves on the same volume are plain renames.
+pub struct CopyEngine {
+    control: Arc<OperationControl>,
+}
+
+impl CopyEngine {
+    pub fn new(control: Arc<OperationControl>) -> Self {
+        Self { control }
+    }
+
+    pub fn run<F>(
+        &self,
+        sources: &[PathBuf],
+        dest_dir: &Path,
+        options: &CopyOptions,
+        on_progress: F,
+    ) -> Result<CopyReport, CopyError>
+    where
+        F: Fn(CopyProgress) + Sync,
+    {
+        if !dest_dir.is_dir() {
+            return Err(CopyError::InvalidPath(dest_dir.display().to_string()));
+        }
+
+        let mut plan = Plan::default();
+        for source in sources {
+            let metadata = fs::symlink_metadata(source)
+                .map_err(|_| CopyError::InvalidPath(source.display().to_string()))?;
+            let Some(name) = source.file_name() else {
+                return Err(CopyError::InvalidPath(source.display().to_string()));
+            };
+            let mut target = dest_dir.join(name);
+
+            if metadata.is_dir() && is_within(dest_dir, source)? {
+                return Err(CopyError::IntoItself(source.display().to_string()));
+            }
+            if fs::symlink_metadata(&target).is_ok() && is_within(&target, source)? {
+                // Same item: only a copy with Rename makes sense, as a duplicate.
+                if options.mode == TransferMode::Copy
+                    && options.overwrite == OverwritePolicy::Rename
+                {
+                    target = unique_name(&target, &plan.claimed);
+                    plan.report.renamed.push(target.clone());
+                } else {
+                    plan.report.skipped.push(target);
+                    continue;
+                }
+            }
+            if options.mode == TransferMode::Move
+                && fs::symlink_metadata(&target).is_err()
+                && !plan.claimed.contains(&target)
+            {
+                // Plan the copy as well, so that a conflict it runs into
+                // aborts the operation before anything has been renamed.
+                let mut fallback = Plan {
+                    claimed: std::mem::take(&mut plan.claimed),
+                    planned_dirs: std::mem::take(&mut plan.planned_dirs),
+                    ..Plan::default()
+                };
+                let planned =
+                    self.plan_entry(source, &metadata, target.clone(), options, &mut fallback);
+                plan.claimed = std::mem::take(&mut fallback.claimed);
+                plan.planned_dirs = std::mem::take(&mut fallback.planned_dirs);
+                planned?;
+                plan.renames.push(PendingRename {
+                    source: source.clone(),
+                    target,
+                    fallback,
+                });
+                continue;
+            }
+
+            self.plan_entry(source, &metadata, target, options, &mut plan)?;
+        }
+
+        for pending in std::mem::take(&mut plan.renames) {
+            if fs::symlink_metadata(&pending.target).is_err()
+                && fs::rename(&pending.source, &pending.target).is_ok()
+            {
+                plan.report.files_copied += 1;
+                continue;
+            }
+            // Another volume: copy and delete instead.
+            plan.absorb(pending.fallback);
+        }
+
+        for dir in &plan.dirs {
+            fs::create_dir_all(dir)?;
+        }
+
+        let transfer = Transfer {
+            control: &self.control,
+            options,
+            bytes_done: AtomicU64::new(0),
+            files_done: AtomicU64::new(0),
+            bytes_total: plan.jobs.iter().map(|job| job.size).sum(),
+            files_total: plan.jobs.len() as u64,
+            throttle: ProgressThrottle::new(PROGRESS_INTERVAL),
+            on_progress: &on_progress,
+            failed: Mutex::new(Vec::new()),
+        };
+        plan.jobs.par_iter().for_each(|job| transfer.run(job));
+
+        let mut report = plan.report;
+        report.cancelled = self.control.is_cancelled();
+        report.failed = transfer.failed.into_inner().unwrap();
+        report.files_copied += transfer.files_done.load(Ordering::Relaxed);
+        report.bytes_copied = transfer.bytes_done.load(Ordering::Relaxed);
+
+        if options.mode == TransferMode::Move {
+            // Directories still holding skipped or failed files stay put.
+            for dir in plan.source_dirs.iter().rev() {
+                let _ = fs::remove_dir(dir);
+            }
+        }
+
+        Ok(report)
+    }
+
+    fn plan_entry(
+        &self,
+        source: &Path,
+        metadata: &fs::Metadata,
+        target: PathBuf,
+        options: &CopyOptions,
+        plan: &mut Plan,
+    ) -> Result<(), CopyError> {
+        if self.control.is_cancelled() {
+            return Err(CopyError::Cancelled);
+        }
+
+        if metadata.is_dir() {
+            // Existing directories are merged; conflicts are resolved per file.
+            let merge = plan.planned_dirs.contains(&target)
+                || (!plan.claimed.contains(&target)
+                    && fs::symlink_metadata(&target).is_ok_and(|existing| existing.is_dir()));
+            let target = if merge {
+                target
+            } else {
+                let Some(target) = resolve_conflict(metadata, target, options.overwrite, plan)?
+                else {
+                    return Ok(());
+                };
+                plan.claimed.insert(target.clone());
+                plan.planned_dirs.insert(target.clone());
+                plan.dirs.push(target.clone());
+                target
+            };
+            plan.source_dirs.push(source.to_path_buf());
+
+            for entry in fs::read_dir(source)? {
+                let entry = entry?;
+                let metadata = entry.metadata()?;
+                self.plan_entry(
+                    &entry.path(),
+                    &metadata,
+                    target.join(entry.file_name()),
+                    options,
+                    plan,
+                )?;
+            }
+            return Ok(());
+        }
+
+        let Some(target) = resolve_conflict(metadata, target, options.overwrite, plan)? else {
+            return Ok(());
+        };
+        plan.claimed.insert(target.clone());
+        plan.jobs.push(FileJob {
+            source: source.to_path_buf(),
+            target,
+            size: metadata.len(),
+            kind: if metadata.is_symlink() {
+                JobKind::Symlink
+            } else {
+                JobKind::File
+            },
+        });
+        Ok(())
+    }
+}
+
+/// Returns the path to write to, or `None` if the entry should be skipped.
+fn resolve_conflict(
+    metadata: &fs::Metadata,
+    target: PathBuf,
+    policy: OverwritePolicy,
+    plan: &mut Plan,
+) -> Result<Option<PathBuf>, CopyError> {
+    let existing = fs::symlink_metadata(&target).ok();
+    let claimed = plan.claimed.contains(&target);
+    if existing.is_none() && !claimed {
+        return Ok(Some(target));
+    }
+    // Only a file on disk that no other source in this batch writes to can be
+    // replaced, and only by another file.
+    let replaceable = !claimed
+        && !metadata.is_dir()
+        && existing.as_ref().is_some_and(|existing| !existing.is_dir());
+
+    match policy {
+        OverwritePolicy::Skip => {
+            plan.report.skipped.push(target);
+            Ok(None)
+        }
+        OverwritePolicy::Rename => {
+            let renamed = unique_name(&target, &plan.claimed);
+            plan.report.renamed.push(renamed.clone());
+            Ok(Some(renamed))
+        }
+        OverwritePolicy::Fail => Err(CopyError::Conflict(target.display().to_string())),
+        _ if !replaceable => Err(CopyError::Conflict(target.display().to_string())),
+        OverwritePolicy::Overwrite => Ok(Some(target)),
+        OverwritePolicy::OverwriteIfNewer => {
+            let target_modified = existing.and_then(|existing| existing.modified().ok());
+            match (metadata.modified().ok(), target_modified) {
+                (Some(source_modified), Some(target_modified))
+                    if source_modified <= target_modified =>
+                {
+                    plan.report.skipped.push(target);
+                    Ok(None)
+                }
+                _ => Ok(Some(target)),
+            }
+        }
+    }
+}
+
+/// Finds the first free `stem (n).ext` name next to `target`.
+pub fn unique_name(target: &Path, claimed: &HashSet<PathBuf>) -> PathBuf {
+    let stem = target
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = target
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+
+    (2..)
+        .map(|n| target.with_file_name(format!("{} ({}){}", stem, n, extension)))
+        .find(|candidate| fs::symlink_metadata(candidate).is_err() && !claimed.contains(candidate))
+        .unwrap()
+}
+
+fn is_within(path: &Path, ancestor: &Path) -> io::Result<bool> {
+    Ok(fs::canonicalize(path)?.starts_with(fs::canonicalize(ancestor)?))
+}
+
+struct Transfer<'a, F> {
+    control: &'a OperationControl,
+    options: &'a CopyOptions,
+    bytes_done: AtomicU64,
+    files_done: AtomicU64,
+    bytes_total: u64,
+    files_total: u64,
+    throttle: ProgressThrottle,
+    on_progress: &'a F,
+    failed: Mutex<Vec<CopyFailure>>,
+}
+
+impl<F> Transfer<'_, F>
+where
+    F: Fn(CopyProgress) + Sync,
+{
+    fn run(&self, job: &FileJob) {
+        if !self.control.checkpoint() {
+            return;
+        }
+
+        // Files merged into an existing directory on the same volume are
+        // still moved with a rename.
+        if self.options.mode == TransferMode::Move && fs::rename(&job.source, &job.target).is_ok() {
+            self.bytes_done.fetch_add(job.size, Ordering::Relaxed);
+            self.files_done.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+
+        let result = match job.kind {
+            JobKind::File => self.copy_file(job),
+            JobKind::Symlink => copy_symlink(&job.source, &job.target),
+        };
+        let result = result.and_then(|()| {
+            if self.options.mode == TransferMode::Move {
+                fs::remove_file(&job.source)?;
+            }
+            Ok(())
+        });
+
+        match result {
+            Ok(()) => {
+                self.files_done.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(_) if self.control.is_cancelled() => {}
+            Err(err) => self.failed.lock().unwrap().push(CopyFailure {
+                path: job.source.clone(),
+                reason: err.to_string(),
+            }),
+        }
+    }
+
+    fn copy_file(&self, job: &FileJob) -> io::Result<()> {
+        let partial = partial_path(&job.target);
+        let result = self.write_partial(job, &partial);
+        if result.is_err() {
+            let _ = fs::remove_file(&partial);
+        }
+        result
+    }
+
+    fn write_partial(&self, job: &FileJob, partial: &Path) -> io::Result<()> {
+        let mut source = File::open(&job.source)?;
+        let metadata = source.metadata()?;
+        let mut target = File::create(partial)?;
+
+        let mut buffer = vec![0; BUFFER_SIZE];
+        let mut written = 0;
+        loop {
+            if !self.control.checkpoint() {
+                return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+            }
+            let read = source.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            target.write_all(&buffer[..read])?;
+            written += read as u64;
+            self.bytes_done.fetch_add(read as u64, Ordering::Relaxed);
+            self.report(job, written);
+        }
+
+        target.set_permissions(metadata.permissions())?;
+        if let Ok(modified) = metadata.modified() {
+            target.set_modified(modified)?;
+        }
+        target.sync_all()?;
+        drop(target);
+
+        if self.options.verify && sha256_file(&job.source)? != sha256_file(partial)? {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "verification failed: checksums differ",
+            ));
+        }
+
+        fs::rename(partial, &job.target)
+    }
+
+    fn report(&self, job: &FileJob, written: u64) {
+        if !self.throttle.ready() {
+            return;
+        }
+        (self.on_progress)(CopyProgress {
+            current_file: job.source.clone(),
+            current_file_bytes: written,
+            current_file_total: job.size,
+            bytes_done: self.bytes_done.load(Ordering::Relaxed),
+            bytes_total: self.bytes_total,
+            files_done: self.files_done.load(Ordering::Relaxed),
+            files_total: self.files_total,
+        });
+    }
+}
+
+fn partial_path(target: &Path) -> PathBuf {
+    let name = target
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    target.with_file_name(format!(".{}.nimbus-part", name))
+}
+
+#[cfg(unix)]
+fn copy_symlink(source: &Path, target: &Path) -> io::Result<()> {
+    let link = fs::read_link(source)?;
+    if fs::symlink_metadata(target).is_ok() {
+        fs::remove_file(target)?;
+    }
+    std::os::unix::fs::symlink(link, target)
+}
+
+#[cfg(windows)]
+fn copy_symlink(source: &Path, target: &Path) -> io::Result<()> {
+    let link = fs::read_link(source)?;
+    if fs::symlink_metadata(target).is_ok() {
+        fs::remove_file(target)?;
+    }
+    if fs::metadata(source).is_ok_and(|metadata| metadata.is_dir()) {
+        std::os::windows::fs::symlink_dir(link, target)
+    } else {
+        std::os::windows::fs::symlink_file(link, target)
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn copy_symlink(source: &Path, target: &Path) -> io::Result<()> {
+    fs::copy(source, target).map(|_| ())
+}
+
+pub fn sha256_file(path: &Path) -> io::Result<[u8; 32]> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; BUFFER_SIZE];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher.finalize().into())
+}
+
+async fn run_transfer(
+    str_sources: Vec<String>,
+    str_dest: String,
+    options: CopyOptions,
+    operation_id: String,
+    on_progress: Channel<CopyProgress>,
+    registry: tauri::State<'_, OperationRegistry>,
+) -> Result<CopyReport, CopyError> {
+    let engine = CopyEngine::new(registry.register(&operation_id));
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let sources: Vec<PathBuf> = str_sources.into_iter().map(PathBuf::from).collect();
+        engine.run(&sources, Path::new(&str_dest), &options, |progress| {
+            let _ = on_progress.send(progress);
+        })
+    })
+    .await;
+
+    registry.finish(&operation_id);
+    result.map_err(|err| CopyError::IoError(err.to_string()))?
+}
+
+#[tauri::command]
+pub async fn copy_files(
+    str_sources: Vec<String>,
+    str_dest: String,
+    options: CopyOptions,
+    operation_id: String,
+    on_progress: Channel<CopyProgress>,
+    registry: tauri::State<'_, OperationRegistry>,
+) -> Result<CopyReport, CopyError> {
+    let options = CopyOptions {
+        mode: TransferMode::Copy,
+        ..options
+    };
+    run_transfer(
+        str_sources,
+        str_dest,
+        options,
+        operation_id,
+        on_progress,
+        registry,
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn move_files(
+    str_sources: Vec<String>,
+    str_dest: String,
+    options: CopyOptions,
+    operation_id: String,
+    on_progress: Channel<CopyProgress>,
+    registry: tauri::State<'_, OperationRegistry>,
+) -> Result<CopyReport, CopyError> {
+    let options = CopyOptions {
+        mode: TransferMode::Move,
+        ..options
+    };
+    run_transfer(
+        str_sources,
+        str_dest,
+        options,
+        operation_id,
+        on_progress,
+        registry,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(
+        sources: &[PathBuf],
+        dest: &Path,
+        overwrite: OverwritePolicy,
+        mode: TransferMode,
+    ) -> Result<CopyReport, CopyError> {
+        let options = CopyOptions {
+            overwrite,
+            verify: false,
+            mode,
+        };
+        CopyEngine::new(Arc::default()).run(sources, dest, &options, |_| {})
+    }
+
+    #[test]
+    fn failed_move_writes_nothing() {
+        let root = tempfile::tempdir().unwrap();
+        let (src, dest) = (root.path().join("src"), root.path().join("dest"));
+        fs::create_dir_all(&src).unwrap();
+        fs::create_dir_all(&dest).unwrap();
+        fs::write(src.join("one"), "1").unwrap();
+        fs::write(src.join("two"), "2").unwrap();
+        fs::write(dest.join("two"), "old").unwrap();
+
+        let result = run(
+            &[src.join("one"), src.join("two")],
+            &dest,
+            OverwritePolicy::Fail,
+            TransferMode::Move,
+        );
+
+        assert!(matches!(result, Err(CopyError::Conflict(_))));
+        assert!(src.join("one").exists());
+        assert!(!dest.join("one").exists());
+    }
+
+    #[test]
+    fn sources_with_the_same_name_do_not_overwrite_each_other() {
+        let root = tempfile::tempdir().unwrap();
+        let dest = root.path().join("dest");
+        fs::create_dir_all(&dest).unwrap();
+        for dir in ["a", "b"] {
+            fs::create_dir_all(root.path().join(dir)).unwrap();
+            fs::write(root.path().join(dir).join("x.txt"), dir).unwrap();
+        }
+        let sources = [root.path().join("a/x.txt"), root.path().join("b/x.txt")];
+
+        let result = run(
+            &sources,
+            &dest,
+            OverwritePolicy::Overwrite,
+            TransferMode::Copy,
+        );
+        assert!(matches!(result, Err(CopyError::Conflict(_))));
+        assert!(!dest.join("x.txt").exists());
+
+        let report = run(&sources, &dest, OverwritePolicy::Rename, TransferMode::Copy)
+            .ok()
+            .unwrap();
+        assert_eq!(report.files_copied, 2);
+        assert_eq!(fs::read_to_string(dest.join("x.txt")).unwrap(), "a");
+        assert_eq!(fs::read_to_string(dest.join("x (2).txt")).unwrap(), "b");
+    }
+
+    #[test]
+    fn file_and_directory_collisions_follow_the_policy() {
+        let root = tempfile::tempdir().unwrap();
+        let (src, dest) = (root.path().join("src"), root.path().join("dest"));
+        fs::create_dir_all(src.join("dir")).unwrap();
+        fs::write(src.join("dir/inner"), "inner").unwrap();
+        fs::write(src.join("file"), "file").unwrap();
+        fs::create_dir_all(dest.join("file")).unwrap();
+        fs::write(dest.join("dir"), "existing").unwrap();
+        let sources = [src.join("dir"), src.join("file")];
+
+        let report = run(&sources, &dest, OverwritePolicy::Skip, TransferMode::Copy)
+            .ok()
+            .unwrap();
+        assert_eq!(report.skipped, vec![dest.join("dir"), dest.join("file")]);
+        assert_eq!(report.files_copied, 0);
+
+        let report = run(&sources, &dest, OverwritePolicy::Rename, TransferMode::Copy)
+            .ok()
+            .unwrap();
+        assert_eq!(
+            report.renamed,
+            vec![dest.join("dir (2)"), dest.join("file (2)")]
+        );
+        assert_eq!(
+            fs::read_to_string(dest.join("dir (2)/inner")).unwrap(),
+            "inner"
+        );
+        assert_eq!(fs::read_to_string(dest.join("file (2)")).unwrap(), "file");
+
+        let result = run(
+            &sources,
+            &dest,
+            OverwritePolicy::Overwrite,
+            TransferMode::Copy,
+        );
+        assert!(matches!(result, Err(CopyError::Conflict(_))));
+    }
+
+    #[test]
+    fn conflict_inside_a_renamed_directory_aborts_before_any_rename() {
+        let root = tempfile::tempdir().unwrap();
+        let dest = root.path().join("dest");
+        fs::create_dir_all(&dest).unwrap();
+        for parent in ["a", "b"] {
+            fs::create_dir_all(root.path().join(parent).join("d")).unwrap();
+            fs::write(root.path().join(parent).join("d/f"), parent).unwrap();
+        }
+        let sources = [root.path().join("a/d"), root.path().join("b/d")];
+
+        let result = run(&sources, &dest, OverwritePolicy::Fail, TransferMode::Move);
+        assert!(matches!(result, Err(CopyError::Conflict(_))));
+        assert!(root.path().join("a/d/f").exists());
+        assert!(!dest.join("d").exists());
+
+        let report = run(&sources, &dest, OverwritePolicy::Rename, TransferMode::Move)
+            .ok()
+            .unwrap();
+        assert_eq!(report.renamed, [dest.join("d/f (2)")]);
+        assert_eq!(fs::read_to_string(dest.join("d/f")).unwrap(), "a");
+        assert_eq!(fs::read_to_string(dest.join("d/f (2)")).unwrap(), "b");
+        assert!(!root.path().join("b/d").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn merged_move_renames_files() {
+        use std::os::unix::fs::MetadataExt;
+
+        let root = tempfile::tempdir().unwrap();
+        let (src, dest) = (root.path().join("src"), root.path().join("dest"));
+        fs::create_dir_all(src.join("d")).unwrap();
+        fs::create_dir_all(dest.join("d")).unwrap();
+        fs::write(src.join("d/new"), "new").unwrap();
+        fs::write(dest.join("d/old"), "old").unwrap();
+        let inode = fs::metadata(src.join("d/new")).unwrap().ino();
+
+        let report = run(
+            &[src.join("d")],
+            &dest,
+            OverwritePolicy::Fail,
+            TransferMode::Move,
+        )
+        .ok()
+        .unwrap();
+        assert_eq!(report.files_copied, 1);
+        assert_eq!(fs::metadata(dest.join("d/new")).unwrap().ino(), inode);
+        assert!(dest.join("d/old").exists());
+        assert!(!src.join("d").exists());
+    }
+
+    #[test]
+    fn cancelled_transfer_returns_what_was_done() {
+        let root = tempfile::tempdir().unwrap();
+        let (src, dest) = (root.path().join("src"), root.path().join("dest"));
+        fs::create_dir_all(&src).unwrap();
+        fs::create_dir_all(&dest).unwrap();
+        fs::write(src.join("renamed"), "renamed").unwrap();
+        fs::write(src.join("copied"), "copied").unwrap();
+        fs::write(dest.join("copied"), "old").unwrap();
+
+        // Paused jobs wait at their first checkpoint, after the top-level renames.
+        let control: Arc<OperationControl> = Arc::default();
+        control.set_paused(true);
+        let engine = CopyEngine::new(control.clone());
+        let (sources, dest_dir) = ([src.join("renamed"), src.join("copied")], dest.clone());
+        let worker = std::thread::spawn(move || {
+            let options = CopyOptions {
+                overwrite: OverwritePolicy::Overwrite,
+                verify: false,
+                mode: TransferMode::Move,
+            };
+            engine.run(&sources, &dest_dir, &options, |_| {})
+        });
+        while !dest.join("renamed").exists() {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        control.cancel();
+
+        let report = worker.join().unwrap().ok().unwrap();
+        assert!(report.cancelled);
+        assert_eq!(report.files_copied, 1);
+        assert_eq!(fs::read_to_string(dest.join("copied")).unwrap(), "old");
+        assert!(src.join("copied").exists());
+    }
+}
@@ -0,0 +1,452 @@
+use std::io;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use serde::Deserialize;
+
+pub enum HostError {
+    InvalidPath(String),
+    NoTerminal,
+    #[cfg_attr(any(windows, target_os = "macos"), allow(dead_code))]
+    Unsupported,
+    IoError(String),
+}
+
+impl serde::Serialize for HostError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let err_msg = match self {
+            HostError::InvalidPath(path) => format!("Invalid Path: {}", path),
+            HostError::NoTerminal => "No terminal emulator found".to_string(),
+            HostError::Unsupported => "Not supported on this platform".to_string(),
+            HostError::IoError(reason) => format!("IO Error: {}", reason),
+        };
+
+        serializer.serialize_str(err_msg.as_str())
+    }
+}
+
+impl From<io::Error> for HostError {
+    fn from(err: io::Error) -> Self {
+        HostError::IoError(err.to_string())
+    }
+}
+
+/// A terminal chosen by the user instead of the detected default.
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminalConfig {
+    /// Executable name or path. On macOS, an application name for `open -a`.
+    pub program: String,
+    /// Extra arguments, where `{path}` is replaced with the directory. The
+    /// directory is always the working directory of the new terminal as well.
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+/// Opens a terminal in `path`, or in its parent directory when `path` is a file.
+pub fn open_terminal_at(path: &Path, config: Option<&TerminalConfig>) -> Result<(), HostError> {
+    let dir = if path.is_dir() {
+        path
+    } else {
+        path.parent()
+            .filter(|parent| parent.is_dir())
+            .ok_or_else(|| HostError::InvalidPath(path.display().to_string()))?
+    };
+
+    let (program, args) = match config {
+        Some(config) => (config.program.clone(), config.args.clone()),
+        None => {
+            let program = detect_terminals()
+                .into_iter()
+                .next()
+                .ok_or(HostError::NoTerminal)?;
+            (program, Vec::new())
+        }
+    };
+    launch_terminal(&program, &terminal_args(&args, dir), dir)
+}
+
+fn terminal_args(args: &[String], dir: &Path) -> Vec<String> {
+    args.iter()
+        .map(|arg| arg.replace("{path}", &dir.to_string_lossy()))
+        .collect()
+}
+
+/// Shows `path` selected in the system file manager.
+pub fn reveal_in_system_manager(path: &Path) -> Result<(), HostError> {
+    if path.symlink_metadata().is_err() {
+        return Err(HostError::InvalidPath(path.display().to_string()));
+    }
+    reveal(path)
+}
+
+/// Opens the system's properties or info dialog for `path`.
+pub fn open_system_properties(path: &Path) -> Result<(), HostError> {
+    if path.symlink_metadata().is_err() {
+        return Err(HostError::InvalidPath(path.display().to_string()));
+    }
+    properties(path)
+}
+
+/// Starts a program without waiting for it, reaping it in the background once it exits.
+#[cfg(not(target_os = "macos"))]
+fn spawn_detached(command: &mut Command) -> Result<(), HostError> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// Looks `program` up on `PATH` unless it already contains a directory.
+#[cfg(not(target_os = "macos"))]
+fn is_installed(program: &str) -> bool {
+    let candidate = Path::new(program);
+    if candidate.components().count() > 1 {
+        return candidate.is_file();
+    }
+    std::env::var_os("PATH")
+        .is_some_and(|paths| std::env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+}
+
+#[cfg(windows)]
+const TERMINALS: &[&str] = &["wt.exe", "pwsh.exe", "powershell.exe", "cmd.exe"];
+
+/// Terminal emulators found on this machine, most preferred first.
+#[cfg(windows)]
+pub fn detect_terminals() -> Vec<String> {
+    TERMINALS
+        .iter()
+        .filter(|program| is_installed(program))
+        .map(|program| program.to_string())
+        .collect()
+}
+
+#[cfg(windows)]
+fn launch_terminal(program: &str, args: &[String], dir: &Path) -> Result<(), HostError> {
+    use std::os::windows::process::CommandExt;
+
+    const CREATE_NEW_CONSOLE: u32 = 0x10;
+    let mut command = Command::new(program);
+    // Windows Terminal does not inherit the working directory of its launcher.
+    if program.eq_ignore_ascii_case("wt.exe") || program.eq_ignore_ascii_case("wt") {
+        command.arg("-d").arg(dir);
+    }
+    command
+        .args(args)
+        .current_dir(dir)
+        .creation_flags(CREATE_NEW_CONSOLE);
+    spawn_detached(&mut command)
+}
+
+#[cfg(windows)]
+fn reveal(path: &Path) -> Result<(), HostError> {
+    use std::os::windows::process::CommandExt;
+
+    // Explorer parses "/select,<path>" itself and needs the path quoted inside
+    // the argument, which the standard argument escaping would break.
+    spawn_detached(Command::new("explorer.exe").raw_arg(format!("/select,\"{}\"", path.display())))
+}
+
+#[cfg(windows)]
+fn properties(path: &Path) -> Result<(), HostError> {
+    use std::os::windows::ffi::OsStrExt;
+
+    use windows_sys::Win32::System::Com::{
+        CoInitializeEx, CoUninitialize, COINIT_APARTMENTTHREADED, COINIT_DISABLE_OLE1DDE,
+    };
+    use windows_sys::Win32::UI::Shell::{
+        ShellExecuteExW, SEE_MASK_INVOKEIDLIST, SEE_MASK_NOASYNC, SHELLEXECUTEINFOW,
+    };
+
+    const SW_SHOWNORMAL: i32 = 1;
+    let wide_path: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let verb: Vec<u16> = "properties".encode_utf16().chain(Some(0)).collect();
+    // SAFETY: SHELLEXECUTEINFOW is plain data, so all zeroes is a valid value.
+    let mut info: SHELLEXECUTEINFOW = unsafe { std::mem::zeroed() };
+    info.cbSize = std::mem::size_of::<SHELLEXECUTEINFOW>() as u32;
+    // The blocking thread may end right after the call, so wait for the shell
+    // to finish starting the verb before returning.
+    info.fMask = SEE_MASK_INVOKEIDLIST | SEE_MASK_NOASYNC;
+    info.lpVerb = verb.as_ptr();
+    info.lpFile = wide_path.as_ptr();
+    info.nShow = SW_SHOWNORMAL;
+
+    // SAFETY: the shell verbs need COM on the calling thread, balanced below
+    // when initialization succeeded.
+    let initialized = unsafe {
+        CoInitializeEx(
+            std::ptr::null(),
+            (COINIT_APARTMENTTHREADED | COINIT_DISABLE_OLE1DDE) as u32,
+        )
+    } >= 0;
+    // SAFETY: `info` and the strings it points to outlive the call.
+    let result = if unsafe { ShellExecuteExW(&mut info) } == 0 {
+        Err(io::Error::last_os_error().into())
+    } else {
+        Ok(())
+    };
+    if initialized {
+        // SAFETY: pairs with the successful CoInitializeEx above.
+        unsafe { CoUninitialize() };
+    }
+    result
+}
+
+#[cfg(target_os = "macos")]
+const TERMINALS: &[&str] = &["iTerm", "Warp", "Terminal"];
+
+#[cfg(target_os = "macos")]
+pub fn detect_terminals() -> Vec<String> {
+    TERMINALS
+        .iter()
+        .filter(|app| {
+            ["/Applications", "/System/Applications/Utilities"]
+                .iter()
+                .any(|dir| Path::new(dir).join(format!("{}.app", app)).is_dir())
+        })
+        .map(|app| app.to_string())
+        .collect()
+}
+
+#[cfg(target_os = "macos")]
+fn launch_terminal(app: &str, args: &[String], dir: &Path) -> Result<(), HostError> {
+    let mut command = Command::new("open");
+    command.arg("-a").arg(app).arg(dir);
+    if !args.is_empty() {
+        command.arg("--args").args(args);
+    }
+    run(&mut command)
+}
+
+#[cfg(target_os = "macos")]
+fn reveal(path: &Path) -> Result<(), HostError> {
+    run(Command::new("open").arg("-R").arg(path))
+}
+
+#[cfg(target_os = "macos")]
+fn properties(path: &Path) -> Result<(), HostError> {
+    let escaped = path
+        .to_string_lossy()
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"");
+    let script = format!(
+        "tell application \"Finder\"\n\
+         activate\n\
+         open information window of (POSIX file \"{}\" as alias)\n\
+         end tell",
+        escaped
+    );
+    run(Command::new("osascript").arg("-e").arg(script))
+}
+
+/// Runs a short-lived helper such as `open` and fails if it does.
+#[cfg(target_os = "macos")]
+fn run(command: &mut Command) -> Result<(), HostError> {
+    let output = command.stdin(Stdio::null()).output()?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(HostError::IoError(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ))
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+const TERMINALS: &[&str] = &[
+    "x-terminal-emulator",
+    "gnome-terminal",
+    "konsole",
+    "xfce4-terminal",
+    "kitty",
+    "alacritty",
+    "wezterm",
+    "foot",
+    "xterm",
+];
+
+/// `$TERMINAL` first, then the Debian alternative, then common emulators.
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn detect_terminals() -> Vec<String> {
+    let preferred = std::env::var("TERMINAL").ok();
+    rank_terminals(preferred.as_deref(), is_installed)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn rank_terminals(preferred: Option<&str>, installed: impl Fn(&str) -> bool) -> Vec<String> {
+    let mut terminals: Vec<String> = Vec::new();
+    for program in preferred
+        .filter(|value| !value.is_empty())
+        .into_iter()
+        .chain(TERMINALS.iter().copied())
+    {
+        if installed(program) && !terminals.iter().any(|known| known == program) {
+            terminals.push(program.to_string());
+        }
+    }
+    terminals
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn launch_terminal(program: &str, args: &[String], dir: &Path) -> Result<(), HostError> {
+    spawn_detached(Command::new(program).args(args).current_dir(dir))
+}
+
+/// Asks the file manager over D-Bus to select the item, which Nautilus, Dolphin,
+/// Nemo and others implement. Falls back to opening the parent directory.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn reveal(path: &Path) -> Result<(), HostError> {
+    let shown = Command::new("dbus-send")
+        .args([
+            "--session",
+            "--print-reply",
+            "--dest=org.freedesktop.FileManager1",
+            "--type=method_call",
+            "/org/freedesktop/FileManager1",
+            "org.freedesktop.FileManager1.ShowItems",
+        ])
+        .arg(format!("array:string:{}", file_uri(path)))
+        .arg("string:")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success());
+    if shown {
+        return Ok(());
+    }
+
+    let parent = path.parent().unwrap_or(path);
+    spawn_detached(Command::new("xdg-open").arg(parent))
+}
+
+/// There is no common properties dialog across Linux desktops.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn properties(_path: &Path) -> Result<(), HostError> {
+    Err(HostError::Unsupported)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn file_uri(path: &Path) -> String {
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut uri = String::from("file://");
+    for &byte in path.as_os_str().as_bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-._~".contains(&byte) {
+            uri.push(byte as char);
+        } else {
+            uri.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    uri
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn detect_terminals() -> Vec<String> {
+    Vec::new()
+}
+
+#[cfg(not(any(unix, windows)))]
+fn launch_terminal(_program: &str, _args: &[String], _dir: &Path) -> Result<(), HostError> {
+    Err(HostError::Unsupported)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn reveal(_path: &Path) -> Result<(), HostError> {
+    Err(HostError::Unsupported)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn properties(_path: &Path) -> Result<(), HostError> {
+    Err(HostError::Unsupported)
+}
+
+/// Runs a host call off the main thread: launching helpers such as `open`,
+/// `osascript` or `dbus-send` can block for as long as the other side takes.
+async fn run_blocking<F>(call: F) -> Result<(), HostError>
+where
+    F: FnOnce() -> Result<(), HostError> + Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(call)
+        .await
+        .map_err(|err| HostError::IoError(err.to_string()))?
+}
+
+#[tauri::command]
+pub async fn open_terminal(
+    str_path: String,
+    terminal: Option<TerminalConfig>,
+) -> Result<(), HostError> {
+    run_blocking(move || open_terminal_at(Path::new(&str_path), terminal.as_ref())).await
+}
+
+#[tauri::command]
+pub fn list_terminals() -> Vec<String> {
+    detect_terminals()
+}
+
+#[tauri::command]
+pub async fn reveal_in_file_manager(str_path: String) -> Result<(), HostError> {
+    run_blocking(move || reveal_in_system_manager(Path::new(&str_path))).await
+}
+
+#[tauri::command]
+pub async fn show_file_properties(str_path: String) -> Result<(), HostError> {
+    run_blocking(move || open_system_properties(Path::new(&str_path))).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_placeholder_is_replaced_in_every_argument() {
+        let args = vec![
+            "--working-directory={path}".to_string(),
+            "-e".to_string(),
+            "ls {path} {path}".to_string(),
+        ];
+        assert_eq!(
+            terminal_args(&args, Path::new("/home/me/my dir")),
+            [
+                "--working-directory=/home/me/my dir",
+                "-e",
+                "ls /home/me/my dir /home/me/my dir"
+            ]
+        );
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn terminal_from_environment_comes_first_once() {
+        let installed = |program: &str| ["kitty", "xterm", "gnome-terminal"].contains(&program);
+        assert_eq!(
+            rank_terminals(Some("kitty"), installed),
+            ["kitty", "gnome-terminal", "xterm"]
+        );
+        assert_eq!(
+            rank_terminals(Some(""), installed),
+            ["gnome-terminal", "kitty", "xterm"]
+        );
+        assert_eq!(
+            rank_terminals(Some("missing"), installed),
+            ["gnome-terminal", "kitty", "xterm"]
+        );
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn file_uri_escapes_reserved_and_non_ascii_bytes() {
+        assert_eq!(
+            file_uri(Path::new("/tmp/a b/#1/caf\u{e9}.txt")),
+            "file:///tmp/a%20b/%231/caf%C3%A9.txt"
+        );
+        assert_eq!(file_uri(Path::new("/a-b_c.~d")), "file:///a-b_c.~d");
+    }
+}
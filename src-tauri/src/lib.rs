@@ -2,6 +2,24 @@ use std::fs;
 
 use serde::Serialize;
 
+use sorting::{NameCollator, SortOptions};
+
+mod checksum;
+mod diff;
+mod dir_size;
+mod directory_listing;
+mod file_ops;
+mod host;
+mod metadata;
+mod name_index;
+mod operations;
+mod recycle_bin;
+mod rename;
+mod restructure;
+mod similarity;
+mod sorting;
+mod watcher;
+
 #[tauri::command]
 fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
@@ -33,7 +51,7 @@ impl From<std::io::Error> for SearchError {
 }
 
 #[tauri::command]
-fn get_files(str_path: &str) -> Result<Vec<String>, SearchError> {
+fn get_files(str_path: &str, sort: Option<SortOptions>) -> Result<Vec<String>, SearchError> {
     let mut file_list = Vec::new();
     let path = std::path::PathBuf::from(str_path);
 
@@ -47,18 +65,64 @@ fn get_files(str_path: &str) -> Result<Vec<String>, SearchError> {
 
         if let Some(file_name) = file_path.file_name() {
             if let Some(file_name_str) = file_name.to_str() {
-                file_list.push(file_name_str.to_string());
+                file_list.push((file_name_str.to_string(), file_path.is_dir()));
             }
         }
     }
 
-    Ok(file_list)
+    // Without sort options the names keep the order the OS returned them in.
+    if let Some(sort) = sort {
+        let collator = NameCollator::new(&sort);
+        collator.sort_entries(&mut file_list, |(name, is_dir)| (name.as_str(), *is_dir));
+    }
+
+    Ok(file_list.into_iter().map(|(name, _)| name).collect())
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
-        .invoke_handler(tauri::generate_handler![greet, get_files])
+        .manage(name_index::NameIndexManager::default())
+        .manage(operations::OperationRegistry::default())
+        .manage(watcher::WatchManager::default())
+        .invoke_handler(tauri::generate_handler![
+            greet,
+            get_files,
+            checksum::generate_checksums,
+            checksum::verify_checksums,
+            diff::compare_files,
+            diff::compare_directories,
+            dir_size::calculate_dir_size,
+            directory_listing::list_directory,
+            file_ops::copy_files,
+            file_ops::move_files,
+            host::open_terminal,
+            host::list_terminals,
+            host::reveal_in_file_manager,
+            host::show_file_properties,
+            metadata::read_file_metadata,
+            name_index::index_directory,
+            name_index::search_directory_index,
+            name_index::drop_directory_index,
+            operations::cancel_operation,
+            operations::pause_operation,
+            operations::resume_operation,
+            recycle_bin::move_to_trash,
+            recycle_bin::list_trash,
+            recycle_bin::restore_from_trash,
+            recycle_bin::empty_trash,
+            rename::plan_batch_rename,
+            rename::apply_batch_rename,
+            restructure::plan_flatten_directory,
+            restructure::plan_split_directory,
+            restructure::plan_restructure_from_mapping,
+            restructure::apply_restructure_plan,
+            restructure::list_restructure_journal,
+            restructure::undo_restructure,
+            similarity::find_similar_files,
+            watcher::watch_directory,
+            watcher::unwatch_directory,
+        ])
         .run(generate_context())
         .expect("failed to run tauri application");
 }
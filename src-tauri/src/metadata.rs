@@ -0,0 +1,537 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::dir_size::allocated_size;
+
+pub enum MetadataError {
+    IoError(String),
+}
+
+impl serde::Serialize for MetadataError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let err_msg = match self {
+            MetadataError::IoError(reason) => format!("IO Error: {}", reason),
+        };
+
+        serializer.serialize_str(err_msg.as_str())
+    }
+}
+
+impl From<io::Error> for MetadataError {
+    fn from(err: io::Error) -> Self {
+        MetadataError::IoError(err.to_string())
+    }
+}
+
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EntryKind {
+    File,
+    Directory,
+    Symlink,
+}
+
+impl EntryKind {
+    pub fn of(file_type: fs::FileType) -> Self {
+        if file_type.is_symlink() {
+            EntryKind::Symlink
+        } else if file_type.is_dir() {
+            EntryKind::Directory
+        } else {
+            EntryKind::File
+        }
+    }
+}
+
+/// The cheap subset of metadata shown in file lists.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryMetadata {
+    pub size: u64,
+    /// Milliseconds since the UNIX epoch.
+    pub modified: Option<u64>,
+    pub created: Option<u64>,
+    pub readonly: bool,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct MetadataOptions {
+    /// List NTFS alternate data streams. Ignored outside Windows.
+    pub include_streams: bool,
+    /// List extended attribute names and sizes. Ignored on Windows.
+    pub include_xattrs: bool,
+    /// Look up owner and group names for Unix ids.
+    pub resolve_owners: bool,
+}
+
+impl Default for MetadataOptions {
+    fn default() -> Self {
+        Self {
+            include_streams: true,
+            include_xattrs: true,
+            resolve_owners: true,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileMetadata {
+    pub path: PathBuf,
+    pub kind: EntryKind,
+    #[serde(flatten)]
+    pub basic: EntryMetadata,
+    pub accessed: Option<u64>,
+    pub allocated_size: u64,
+    pub symlink_target: Option<PathBuf>,
+    pub hidden: bool,
+    pub windows: Option<WindowsMetadata>,
+    pub unix: Option<UnixMetadata>,
+    pub xattrs: Vec<ExtendedAttribute>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowsMetadata {
+    /// Raw `FILE_ATTRIBUTE_*` bits.
+    pub attributes: u32,
+    pub hidden: bool,
+    pub system: bool,
+    pub archive: bool,
+    pub compressed: bool,
+    pub encrypted: bool,
+    /// Named NTFS data streams, without the unnamed main stream.
+    pub streams: Vec<AlternateStream>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlternateStream {
+    pub name: String,
+    pub size: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnixMetadata {
+    /// File type and permission bits as in `st_mode`.
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+    pub owner: Option<String>,
+    pub group: Option<String>,
+    pub inode: u64,
+    pub links: u64,
+    /// BSD file flags such as `UF_HIDDEN` and `UF_IMMUTABLE`. Not available on Linux.
+    pub flags: Option<u32>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtendedAttribute {
+    pub name: String,
+    pub size: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetadataFailure {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+#[derive(Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetadataReport {
+    /// In the order the paths were given, minus failures.
+    pub entries: Vec<FileMetadata>,
+    pub failures: Vec<MetadataFailure>,
+}
+
+/// Reads full metadata, including platform specific extras, for many paths at once.
+///
+/// Symlinks are described themselves rather than their targets.
+pub struct MetadataReader {
+    options: MetadataOptions,
+}
+
+impl MetadataReader {
+    pub fn new(options: MetadataOptions) -> Self {
+        Self { options }
+    }
+
+    pub fn read(&self, paths: &[PathBuf]) -> MetadataReport {
+        let results: Vec<io::Result<FileMetadata>> =
+            paths.par_iter().map(|path| self.read_one(path)).collect();
+
+        let mut report = MetadataReport::default();
+        for (path, result) in paths.iter().zip(results) {
+            match result {
+                Ok(metadata) => report.entries.push(metadata),
+                Err(err) => report.failures.push(MetadataFailure {
+                    path: path.clone(),
+                    reason: err.to_string(),
+                }),
+            }
+        }
+        report
+    }
+
+    fn read_one(&self, path: &Path) -> io::Result<FileMetadata> {
+        let metadata = fs::symlink_metadata(path)?;
+        let kind = EntryKind::of(metadata.file_type());
+        let windows = windows_metadata(path, &metadata, &self.options);
+        let unix = unix_metadata(&metadata, &self.options);
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+            || windows.as_ref().is_some_and(|windows| windows.hidden);
+
+        Ok(FileMetadata {
+            path: path.to_path_buf(),
+            kind,
+            basic: basic_metadata(&metadata),
+            accessed: metadata.accessed().ok().and_then(unix_millis),
+            allocated_size: allocated_size(path, &metadata),
+            symlink_target: match kind {
+                EntryKind::Symlink => fs::read_link(path).ok(),
+                _ => None,
+            },
+            hidden,
+            windows,
+            unix,
+            xattrs: if self.options.include_xattrs {
+                extended_attributes(path)
+            } else {
+                Vec::new()
+            },
+        })
+    }
+}
+
+/// Reads [`EntryMetadata`] without following symlinks.
+pub fn entry_metadata(path: &Path) -> Option<EntryMetadata> {
+    fs::symlink_metadata(path)
+        .ok()
+        .map(|metadata| basic_metadata(&metadata))
+}
+
+fn basic_metadata(metadata: &fs::Metadata) -> EntryMetadata {
+    EntryMetadata {
+        size: metadata.len(),
+        modified: metadata.modified().ok().and_then(unix_millis),
+        created: metadata.created().ok().and_then(unix_millis),
+        readonly: metadata.permissions().readonly(),
+    }
+}
+
+pub fn unix_millis(time: SystemTime) -> Option<u64> {
+    time.duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|elapsed| elapsed.as_millis() as u64)
+}
+
+#[cfg(windows)]
+fn windows_metadata(
+    path: &Path,
+    metadata: &fs::Metadata,
+    options: &MetadataOptions,
+) -> Option<WindowsMetadata> {
+    use std::os::windows::fs::MetadataExt;
+
+    use windows_sys::Win32::Storage::FileSystem::{
+        FILE_ATTRIBUTE_ARCHIVE, FILE_ATTRIBUTE_COMPRESSED, FILE_ATTRIBUTE_ENCRYPTED,
+        FILE_ATTRIBUTE_HIDDEN, FILE_ATTRIBUTE_SYSTEM,
+    };
+
+    let attributes = metadata.file_attributes();
+    Some(WindowsMetadata {
+        attributes,
+        hidden: attributes & FILE_ATTRIBUTE_HIDDEN != 0,
+        system: attributes & FILE_ATTRIBUTE_SYSTEM != 0,
+        archive: attributes & FILE_ATTRIBUTE_ARCHIVE != 0,
+        compressed: attributes & FILE_ATTRIBUTE_COMPRESSED != 0,
+        encrypted: attributes & FILE_ATTRIBUTE_ENCRYPTED != 0,
+        streams: if options.include_streams {
+            alternate_streams(path)
+        } else {
+            Vec::new()
+        },
+    })
+}
+
+#[cfg(not(windows))]
+fn windows_metadata(
+    _path: &Path,
+    _metadata: &fs::Metadata,
+    _options: &MetadataOptions,
+) -> Option<WindowsMetadata> {
+    None
+}
+
+#[cfg(windows)]
+fn alternate_streams(path: &Path) -> Vec<AlternateStream> {
+    use std::os::windows::ffi::OsStrExt;
+
+    use windows_sys::Win32::Foundation::INVALID_HANDLE_VALUE;
+    use windows_sys::Win32::Storage::FileSystem::{
+        FindClose, FindFirstStreamW, FindNextStreamW, FindStreamInfoStandard,
+        WIN32_FIND_STREAM_DATA,
+    };
+
+    let wide_path: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    // SAFETY: WIN32_FIND_STREAM_DATA is plain data, so all zeroes is a valid value.
+    let mut data: WIN32_FIND_STREAM_DATA = unsafe { std::mem::zeroed() };
+    let data_ptr = &mut data as *mut WIN32_FIND_STREAM_DATA as *mut _;
+    // SAFETY: `wide_path` is NUL-terminated and `data` outlives the find handle.
+    let handle =
+        unsafe { FindFirstStreamW(wide_path.as_ptr(), FindStreamInfoStandard, data_ptr, 0) };
+    if handle == INVALID_HANDLE_VALUE {
+        return Vec::new();
+    }
+
+    let mut streams = Vec::new();
+    loop {
+        let len = data
+            .cStreamName
+            .iter()
+            .position(|&unit| unit == 0)
+            .unwrap_or(data.cStreamName.len());
+        // Names look like ":name:$DATA"; the main stream is "::$DATA".
+        let name = String::from_utf16_lossy(&data.cStreamName[..len]);
+        let name = name.strip_prefix(':').unwrap_or(&name);
+        let name = name.strip_suffix(":$DATA").unwrap_or(name);
+        if !name.is_empty() {
+            streams.push(AlternateStream {
+                name: name.to_string(),
+                size: data.StreamSize as u64,
+            });
+        }
+        // SAFETY: `handle` is a valid find handle until FindClose below.
+        if unsafe { FindNextStreamW(handle, data_ptr) } == 0 {
+            break;
+        }
+    }
+    // SAFETY: `handle` came from FindFirstStreamW and is closed once.
+    unsafe { FindClose(handle) };
+    streams
+}
+
+#[cfg(unix)]
+fn unix_metadata(metadata: &fs::Metadata, options: &MetadataOptions) -> Option<UnixMetadata> {
+    use std::os::unix::fs::MetadataExt;
+
+    let (owner, group) = if options.resolve_owners {
+        (
+            uzers::get_user_by_uid(metadata.uid())
+                .map(|user| user.name().to_string_lossy().into_owned()),
+            uzers::get_group_by_gid(metadata.gid())
+                .map(|group| group.name().to_string_lossy().into_owned()),
+        )
+    } else {
+        (None, None)
+    };
+
+    Some(UnixMetadata {
+        mode: metadata.mode(),
+        uid: metadata.uid(),
+        gid: metadata.gid(),
+        owner,
+        group,
+        inode: metadata.ino(),
+        links: metadata.nlink(),
+        flags: bsd_flags(metadata),
+    })
+}
+
+#[cfg(not(unix))]
+fn unix_metadata(_metadata: &fs::Metadata, _options: &MetadataOptions) -> Option<UnixMetadata> {
+    None
+}
+
+#[cfg(target_os = "macos")]
+fn bsd_flags(metadata: &fs::Metadata) -> Option<u32> {
+    use std::os::macos::fs::MetadataExt;
+
+    Some(metadata.st_flags())
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn bsd_flags(_metadata: &fs::Metadata) -> Option<u32> {
+    None
+}
+
+#[cfg(unix)]
+fn extended_attributes(path: &Path) -> Vec<ExtendedAttribute> {
+    // xattr::list and xattr::get do not follow symlinks.
+    let Ok(names) = xattr::list(path) else {
+        return Vec::new();
+    };
+    names
+        .map(|name| ExtendedAttribute {
+            size: xattr::get(path, &name)
+                .ok()
+                .flatten()
+                .map_or(0, |value| value.len() as u64),
+            name: name.to_string_lossy().into_owned(),
+        })
+        .collect()
+}
+
+#[cfg(not(unix))]
+fn extended_attributes(_path: &Path) -> Vec<ExtendedAttribute> {
+    Vec::new()
+}
+
+#[tauri::command]
+pub async fn read_file_metadata(
+    str_paths: Vec<String>,
+    options: Option<MetadataOptions>,
+) -> Result<MetadataReport, MetadataError> {
+    let reader = MetadataReader::new(options.unwrap_or_default());
+    tauri::async_runtime::spawn_blocking(move || {
+        let paths: Vec<PathBuf> = str_paths.into_iter().map(PathBuf::from).collect();
+        reader.read(&paths)
+    })
+    .await
+    .map_err(|err| MetadataError::IoError(err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(report: &MetadataReport) -> Vec<PathBuf> {
+        report
+            .entries
+            .iter()
+            .map(|entry| entry.path.clone())
+            .collect()
+    }
+
+    #[test]
+    fn entries_come_back_in_input_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut inputs = Vec::new();
+        for index in 0..32 {
+            let path = dir.path().join(format!("file{}", 31 - index));
+            fs::write(&path, vec![0; index]).unwrap();
+            inputs.push(path);
+        }
+
+        let report = MetadataReader::new(MetadataOptions::default()).read(&inputs);
+
+        assert!(report.failures.is_empty());
+        assert!(paths(&report) == inputs);
+        for (index, entry) in report.entries.iter().enumerate() {
+            assert!(entry.basic.size == index as u64);
+        }
+    }
+
+    #[test]
+    fn failures_are_reported_per_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = dir.path().join("first");
+        let missing = dir.path().join("missing");
+        let last = dir.path().join("last");
+        fs::write(&first, b"").unwrap();
+        fs::write(&last, b"").unwrap();
+
+        let report = MetadataReader::new(MetadataOptions::default()).read(&[
+            first.clone(),
+            missing.clone(),
+            last.clone(),
+        ]);
+
+        assert!(paths(&report) == [first, last]);
+        assert!(report.failures.len() == 1);
+        assert!(report.failures[0].path == missing);
+        assert!(!report.failures[0].reason.is_empty());
+    }
+
+    #[test]
+    fn dot_files_are_hidden() {
+        let dir = tempfile::tempdir().unwrap();
+        let hidden = dir.path().join(".hidden");
+        let visible = dir.path().join("visible");
+        fs::write(&hidden, b"").unwrap();
+        fs::write(&visible, b"").unwrap();
+
+        let report = MetadataReader::new(MetadataOptions::default()).read(&[hidden, visible]);
+
+        assert!(report.entries[0].hidden);
+        assert!(!report.entries[1].hidden);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unix_fields_describe_the_file() {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file");
+        fs::write(&path, b"data").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+        fs::hard_link(&path, dir.path().join("link")).unwrap();
+        let expected = fs::metadata(&path).unwrap();
+
+        let report = MetadataReader::new(MetadataOptions::default()).read(&[path]);
+
+        let unix = report.entries[0].unix.as_ref().unwrap();
+        assert!(unix.mode & 0o777 == 0o640);
+        assert!(unix.mode & 0o170000 == 0o100000);
+        assert!(unix.uid == expected.uid());
+        assert!(unix.gid == expected.gid());
+        assert!(unix.inode == expected.ino());
+        assert!(unix.links == 2);
+        let owner = uzers::get_user_by_uid(expected.uid())
+            .map(|user| user.name().to_string_lossy().into_owned());
+        let group = uzers::get_group_by_gid(expected.gid())
+            .map(|group| group.name().to_string_lossy().into_owned());
+        assert!(unix.owner == owner);
+        assert!(unix.group == group);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn owners_are_only_resolved_on_request() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file");
+        fs::write(&path, b"").unwrap();
+
+        let report = MetadataReader::new(MetadataOptions {
+            resolve_owners: false,
+            ..MetadataOptions::default()
+        })
+        .read(&[path]);
+
+        let unix = report.entries[0].unix.as_ref().unwrap();
+        assert!(unix.owner.is_none());
+        assert!(unix.group.is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_describe_the_link_itself() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("target");
+        let link = dir.path().join("link");
+        fs::create_dir(&target).unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        let report = MetadataReader::new(MetadataOptions::default()).read(&[link]);
+
+        let entry = &report.entries[0];
+        assert!(matches!(entry.kind, EntryKind::Symlink));
+        assert!(entry.symlink_target.as_deref() == Some(target.as_path()));
+    }
+}
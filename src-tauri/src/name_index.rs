@@ -0,0 +1,462 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::io;
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+
+use serde::{Deserialize, Serialize};
+
+use crate::watcher::{WatchError, WatchEvent, WatchEventKind, WatchManager};
+
+pub enum IndexError {
+    InvalidPath(String),
+    AlreadyIndexed(String),
+    NotIndexed(String),
+    NotifyError(String),
+    IoError(String),
+}
+
+impl serde::Serialize for IndexError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let err_msg = match self {
+            IndexError::InvalidPath(path) => format!("Invalid Path: {}", path),
+            IndexError::AlreadyIndexed(path) => format!("Already Indexed: {}", path),
+            IndexError::NotIndexed(path) => format!("Not Indexed: {}", path),
+            IndexError::NotifyError(reason) => format!("Notify Error: {}", reason),
+            IndexError::IoError(reason) => format!("IO Error: {}", reason),
+        };
+
+        serializer.serialize_str(err_msg.as_str())
+    }
+}
+
+impl From<io::Error> for IndexError {
+    fn from(err: io::Error) -> Self {
+        IndexError::IoError(err.to_string())
+    }
+}
+
+impl From<WatchError> for IndexError {
+    fn from(err: WatchError) -> Self {
+        match err {
+            WatchError::InvalidPath(path) => IndexError::InvalidPath(path),
+            WatchError::AlreadyWatched(path) => IndexError::AlreadyIndexed(path),
+            WatchError::NotifyError(reason) => IndexError::NotifyError(reason),
+        }
+    }
+}
+
+/// How a query is matched against file names, ignoring case.
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NameMatch {
+    Exact,
+    Prefix,
+    /// Matches the end of the name, such as ".tar.gz".
+    Suffix,
+    Contains,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexHit {
+    pub path: PathBuf,
+    pub is_dir: bool,
+}
+
+type NameKey = (String, Arc<Path>);
+
+/// Every path below one root, with its lowercase name and the reverse of it in
+/// sorted sets, so prefix and suffix queries are range scans and substring
+/// queries scan names in memory instead of walking the tree.
+pub struct NameIndex {
+    root: PathBuf,
+    entries: BTreeMap<Arc<Path>, bool>,
+    by_name: BTreeSet<NameKey>,
+    by_reversed: BTreeSet<NameKey>,
+}
+
+impl NameIndex {
+    pub fn build(root: &Path) -> Result<Self, IndexError> {
+        if !root.is_dir() {
+            return Err(IndexError::InvalidPath(root.display().to_string()));
+        }
+        fs::read_dir(root)?;
+
+        let mut index = Self {
+            root: root.to_path_buf(),
+            entries: BTreeMap::new(),
+            by_name: BTreeSet::new(),
+            by_reversed: BTreeSet::new(),
+        };
+        index.insert_children(root);
+        Ok(index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Matching paths in name order, or reversed-name order for suffix queries.
+    pub fn search(&self, query: &str, mode: NameMatch, limit: usize) -> Vec<IndexHit> {
+        let query = query.to_lowercase();
+        let hit = |(_, path): &NameKey| IndexHit {
+            path: path.to_path_buf(),
+            is_dir: self.entries[path],
+        };
+
+        match mode {
+            NameMatch::Exact => starting_with(&self.by_name, &query)
+                .take_while(|(name, _)| *name == query)
+                .take(limit)
+                .map(hit)
+                .collect(),
+            NameMatch::Prefix => starting_with(&self.by_name, &query)
+                .take(limit)
+                .map(hit)
+                .collect(),
+            NameMatch::Suffix => {
+                let reversed: String = query.chars().rev().collect();
+                starting_with(&self.by_reversed, &reversed)
+                    .take(limit)
+                    .map(hit)
+                    .collect()
+            }
+            NameMatch::Contains => self
+                .by_name
+                .iter()
+                .filter(|(name, _)| name.contains(query.as_str()))
+                .take(limit)
+                .map(hit)
+                .collect(),
+        }
+    }
+
+    /// Brings the index in line with the file system for the paths in a batch
+    /// of watcher events. Each path is checked on disk, so the order of events
+    /// and coalescing by the watcher do not matter. A `Rescan` rebuilds the
+    /// affected subtree, since the events for it may be incomplete.
+    pub fn apply(&mut self, events: &[WatchEvent]) {
+        for event in events {
+            if event.kind == WatchEventKind::Rescan {
+                self.rebuild(&event.path);
+                continue;
+            }
+            if let Some(from) = &event.from {
+                self.remove_tree(from);
+            }
+            if event.path == self.root {
+                if fs::symlink_metadata(&self.root).is_err() {
+                    let root = self.root.clone();
+                    self.remove_tree(&root);
+                }
+                continue;
+            }
+            if event.path.starts_with(&self.root) {
+                self.refresh(&event.path);
+            }
+        }
+    }
+
+    /// Walks `path` again, or the whole root if `path` is the root or outside it.
+    fn rebuild(&mut self, path: &Path) {
+        if path == self.root || !path.starts_with(&self.root) {
+            let root = self.root.clone();
+            self.remove_tree(&root);
+            self.insert_children(&root);
+            return;
+        }
+        self.remove_tree(path);
+        self.refresh(path);
+    }
+
+    fn refresh(&mut self, path: &Path) {
+        let Ok(metadata) = fs::symlink_metadata(path) else {
+            self.remove_tree(path);
+            return;
+        };
+        let is_dir = metadata.is_dir();
+        // A directory that is already known only changed its contents, which
+        // arrive as events of their own.
+        if self.entries.get(path) == Some(&is_dir) {
+            return;
+        }
+
+        self.remove_tree(path);
+        self.insert(path, is_dir);
+        if is_dir {
+            self.insert_children(path);
+        }
+    }
+
+    fn insert(&mut self, path: &Path, is_dir: bool) {
+        let path: Arc<Path> = Arc::from(path);
+        if self.entries.insert(path.clone(), is_dir).is_some() {
+            return;
+        }
+        let (name, reversed) = name_keys(&path);
+        self.by_name.insert((name, path.clone()));
+        self.by_reversed.insert((reversed, path));
+    }
+
+    /// Adds everything below `dir` without following symbolic links.
+    fn insert_children(&mut self, dir: &Path) {
+        let mut pending = vec![dir.to_path_buf()];
+        while let Some(dir) = pending.pop() {
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let Ok(file_type) = entry.file_type() else {
+                    continue;
+                };
+                let path = entry.path();
+                self.insert(&path, file_type.is_dir());
+                if file_type.is_dir() {
+                    pending.push(path);
+                }
+            }
+        }
+    }
+
+    /// Removes `path` and everything below it.
+    fn remove_tree(&mut self, path: &Path) {
+        // Paths order by component, so a directory's descendants follow it directly.
+        let removed: Vec<Arc<Path>> = self
+            .entries
+            .range::<Path, _>((Bound::Included(path), Bound::Unbounded))
+            .map(|(entry, _)| entry)
+            .take_while(|entry| entry.starts_with(path))
+            .cloned()
+            .collect();
+        for entry in removed {
+            self.entries.remove(&entry);
+            let (name, reversed) = name_keys(&entry);
+            self.by_name.remove(&(name, entry.clone()));
+            self.by_reversed.remove(&(reversed, entry));
+        }
+    }
+}
+
+fn name_keys(path: &Path) -> (String, String) {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let reversed = name.chars().rev().collect();
+    (name, reversed)
+}
+
+fn starting_with<'a>(
+    keys: &'a BTreeSet<NameKey>,
+    prefix: &'a str,
+) -> impl Iterator<Item = &'a NameKey> + 'a {
+    let first: NameKey = (prefix.to_string(), Arc::from(Path::new("")));
+    keys.range(first..)
+        .take_while(move |(name, _)| name.starts_with(prefix))
+}
+
+/// Name indexes by root. Each one is updated from its own recursive watch,
+/// separate from the watches the UI holds through `WatchManager`, and the parts
+/// of it the watch reports as possibly missed are walked again.
+#[derive(Clone, Default)]
+pub struct NameIndexManager {
+    watcher: Arc<WatchManager>,
+    indexes: Arc<Mutex<HashMap<PathBuf, Arc<RwLock<NameIndex>>>>>,
+}
+
+impl NameIndexManager {
+    /// Indexes `root` and keeps the index current until `remove` is called.
+    /// Blocks while the tree is walked; returns the number of entries.
+    pub fn add(&self, root: &Path) -> Result<usize, IndexError> {
+        // Watch before walking, so changes made during the walk are applied
+        // once it is done.
+        let mut batches = self.watcher.watch(root, true)?;
+        let index = match NameIndex::build(root) {
+            Ok(index) => index,
+            Err(err) => {
+                self.watcher.unwatch(root);
+                return Err(err);
+            }
+        };
+        let len = index.len();
+
+        let index = Arc::new(RwLock::new(index));
+        self.indexes
+            .lock()
+            .unwrap()
+            .insert(root.to_path_buf(), index.clone());
+        // Ends once the watch is removed.
+        thread::spawn(move || {
+            while let Some(batch) = batches.blocking_recv() {
+                index.write().unwrap().apply(&batch);
+            }
+        });
+        Ok(len)
+    }
+
+    pub fn remove(&self, root: &Path) -> bool {
+        self.watcher.unwatch(root);
+        self.indexes.lock().unwrap().remove(root).is_some()
+    }
+
+    pub fn search(
+        &self,
+        root: &Path,
+        query: &str,
+        mode: NameMatch,
+        limit: usize,
+    ) -> Result<Vec<IndexHit>, IndexError> {
+        let index = self
+            .indexes
+            .lock()
+            .unwrap()
+            .get(root)
+            .cloned()
+            .ok_or_else(|| IndexError::NotIndexed(root.display().to_string()))?;
+        let hits = index.read().unwrap().search(query, mode, limit);
+        Ok(hits)
+    }
+}
+
+#[tauri::command]
+pub async fn index_directory(
+    str_path: String,
+    manager: tauri::State<'_, NameIndexManager>,
+) -> Result<usize, IndexError> {
+    let manager = manager.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || manager.add(Path::new(&str_path)))
+        .await
+        .map_err(|err| IndexError::IoError(err.to_string()))?
+}
+
+#[tauri::command]
+pub fn search_directory_index(
+    str_path: &str,
+    query: &str,
+    mode: NameMatch,
+    limit: Option<usize>,
+    manager: tauri::State<NameIndexManager>,
+) -> Result<Vec<IndexHit>, IndexError> {
+    manager.search(
+        Path::new(str_path),
+        query,
+        mode,
+        limit.unwrap_or(usize::MAX),
+    )
+}
+
+#[tauri::command]
+pub fn drop_directory_index(str_path: &str, manager: tauri::State<NameIndexManager>) -> bool {
+    manager.remove(Path::new(str_path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(hits: Vec<IndexHit>, root: &Path) -> Vec<String> {
+        hits.into_iter()
+            .map(|hit| hit.path.strip_prefix(root).unwrap().display().to_string())
+            .collect()
+    }
+
+    fn event(kind: WatchEventKind, path: PathBuf, from: Option<PathBuf>) -> WatchEvent {
+        WatchEvent {
+            kind,
+            path,
+            from,
+            reason: None,
+        }
+    }
+
+    fn tree() -> tempfile::TempDir {
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir_all(root.path().join("src/deep")).unwrap();
+        fs::create_dir_all(root.path().join("srcs")).unwrap();
+        for file in [
+            "Readme.md",
+            "src/main.rs",
+            "src/deep/data.tar.gz",
+            "srcs/lib.rs",
+        ] {
+            fs::write(root.path().join(file), "").unwrap();
+        }
+        root
+    }
+
+    #[test]
+    fn searches_by_name_ignoring_case() {
+        let root = tree();
+        let index = NameIndex::build(root.path()).ok().unwrap();
+        let search = |query, mode| names(index.search(query, mode, usize::MAX), root.path());
+
+        assert_eq!(search("README.MD", NameMatch::Exact), ["Readme.md"]);
+        assert_eq!(search("src", NameMatch::Prefix), ["src", "srcs"]);
+        assert_eq!(
+            search(".RS", NameMatch::Suffix),
+            ["srcs/lib.rs", "src/main.rs"]
+        );
+        assert_eq!(search("tar", NameMatch::Contains), ["src/deep/data.tar.gz"]);
+        assert_eq!(index.search("", NameMatch::Prefix, 2).len(), 2);
+    }
+
+    #[test]
+    fn remove_tree_keeps_siblings_with_the_same_prefix() {
+        let root = tree();
+        let mut index = NameIndex::build(root.path()).ok().unwrap();
+        index.remove_tree(&root.path().join("src"));
+
+        let all = names(index.search("", NameMatch::Prefix, usize::MAX), root.path());
+        assert_eq!(all, ["srcs/lib.rs", "Readme.md", "srcs"]);
+        assert!(index.search("main", NameMatch::Prefix, 1).is_empty());
+    }
+
+    #[test]
+    fn renames_move_whole_subtrees() {
+        let root = tree();
+        let mut index = NameIndex::build(root.path()).ok().unwrap();
+        let (from, to) = (root.path().join("src"), root.path().join("moved"));
+        fs::rename(&from, &to).unwrap();
+        index.apply(&[event(WatchEventKind::Renamed, to, Some(from))]);
+
+        let hits = names(
+            index.search("data", NameMatch::Prefix, usize::MAX),
+            root.path(),
+        );
+        assert_eq!(hits, ["moved/deep/data.tar.gz"]);
+        assert_eq!(index.len(), 7);
+    }
+
+    #[test]
+    fn rescan_picks_up_changes_without_events() {
+        let root = tree();
+        let mut index = NameIndex::build(root.path()).ok().unwrap();
+        fs::remove_file(root.path().join("src/main.rs")).unwrap();
+        fs::write(root.path().join("src/deep/new.rs"), "").unwrap();
+        fs::write(root.path().join("top.rs"), "").unwrap();
+
+        index.apply(&[event(WatchEventKind::Rescan, root.path().join("src"), None)]);
+        let hits = names(
+            index.search(".rs", NameMatch::Suffix, usize::MAX),
+            root.path(),
+        );
+        assert_eq!(hits, ["srcs/lib.rs", "src/deep/new.rs"]);
+
+        index.apply(&[event(
+            WatchEventKind::Rescan,
+            root.path().to_path_buf(),
+            None,
+        )]);
+        let hits = names(
+            index.search(".rs", NameMatch::Suffix, usize::MAX),
+            root.path(),
+        );
+        assert_eq!(hits, ["srcs/lib.rs", "top.rs", "src/deep/new.rs"]);
+    }
+}
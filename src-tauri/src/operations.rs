@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+/// Cancel and pause state shared between a running operation and the frontend.
+#[derive(Default)]
+pub struct OperationControl {
+    cancelled: AtomicBool,
+    paused: Mutex<bool>,
+    resumed: Condvar,
+}
+
+impl OperationControl {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+        self.set_paused(false);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        *self.paused.lock().unwrap() = paused;
+        self.resumed.notify_all();
+    }
+
+    /// Blocks while the operation is paused. Returns `false` once it has been cancelled.
+    pub fn checkpoint(&self) -> bool {
+        let mut paused = self.paused.lock().unwrap();
+        while *paused {
+            paused = self.resumed.wait(paused).unwrap();
+        }
+        !self.is_cancelled()
+    }
+}
+
+/// Controls for long-running commands, keyed by an id chosen by the frontend.
+#[derive(Default)]
+pub struct OperationRegistry {
+    controls: Mutex<HashMap<String, Arc<OperationControl>>>,
+}
+
+impl OperationRegistry {
+    pub fn register(&self, id: &str) -> Arc<OperationControl> {
+        let control = Arc::new(OperationControl::default());
+        self.controls
+            .lock()
+            .unwrap()
+            .insert(id.to_string(), control.clone());
+        control
+    }
+
+    pub fn finish(&self, id: &str) {
+        self.controls.lock().unwrap().remove(id);
+    }
+
+    fn with_control(&self, id: &str, f: impl FnOnce(&OperationControl)) -> bool {
+        match self.controls.lock().unwrap().get(id) {
+            Some(control) => {
+                f(control);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Rate limit for progress updates sent from worker threads.
+pub struct ProgressThrottle {
+    interval: Duration,
+    last_report: Mutex<Instant>,
+}
+
+impl ProgressThrottle {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last_report: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Returns `true` at most once per interval across all threads.
+    pub fn ready(&self) -> bool {
+        // Whichever worker holds the lock reports; the others just carry on.
+        let Ok(mut last_report) = self.last_report.try_lock() else {
+            return false;
+        };
+        if last_report.elapsed() < self.interval {
+            return false;
+        }
+        *last_report = Instant::now();
+        true
+    }
+}
+
+#[tauri::command]
+pub fn cancel_operation(id: &str, registry: tauri::State<OperationRegistry>) -> bool {
+    registry.with_control(id, OperationControl::cancel)
+}
+
+#[tauri::command]
+pub fn pause_operation(id: &str, registry: tauri::State<OperationRegistry>) -> bool {
+    registry.with_control(id, |control| control.set_paused(true))
+}
+
+#[tauri::command]
+pub fn resume_operation(id: &str, registry: tauri::State<OperationRegistry>) -> bool {
+    registry.with_control(id, |control| control.set_paused(false))
+}
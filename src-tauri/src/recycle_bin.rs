@@ -0,0 +1,297 @@
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+pub enum TrashError {
+    InvalidPath(String),
+    NotFound(String),
+    RestoreCollision(String),
+    #[cfg_attr(
+        not(any(target_os = "macos", target_os = "ios", target_os = "android")),
+        allow(dead_code)
+    )]
+    Unsupported,
+    SystemError(String),
+}
+
+impl serde::Serialize for TrashError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let err_msg = match self {
+            TrashError::InvalidPath(path) => format!("Invalid Path: {}", path),
+            TrashError::NotFound(id) => format!("Not In Trash: {}", id),
+            TrashError::RestoreCollision(path) => format!("Restore Collision: {}", path),
+            TrashError::Unsupported => "Not supported on this platform".to_string(),
+            TrashError::SystemError(reason) => format!("Trash Error: {}", reason),
+        };
+
+        serializer.serialize_str(err_msg.as_str())
+    }
+}
+
+/// An item currently in the trash, as shown to the frontend.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashEntry {
+    /// Platform identifier used to restore the item.
+    pub id: String,
+    pub name: String,
+    pub original_path: PathBuf,
+    /// Seconds since the UNIX epoch.
+    pub time_deleted: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashFailure {
+    /// The path or trash id that failed.
+    pub item: String,
+    pub reason: TrashError,
+}
+
+/// Outcome of a batch trash operation. One failing item does not stop the rest.
+#[derive(Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashReport {
+    /// Paths or ids handled successfully, in the order they were given.
+    pub done: Vec<String>,
+    pub failures: Vec<TrashFailure>,
+}
+
+impl TrashReport {
+    fn push(&mut self, item: String, result: Result<(), TrashError>) {
+        match result {
+            Ok(()) => self.done.push(item),
+            Err(reason) => self.failures.push(TrashFailure { item, reason }),
+        }
+    }
+}
+
+/// The platform recycle bin: the Windows Recycle Bin, the XDG trash on Linux
+/// and BSD, and `~/.Trash` on macOS.
+///
+/// macOS only supports moving files to the trash; listing, restoring and
+/// emptying return `TrashError::Unsupported` there.
+pub struct Trash;
+
+impl Trash {
+    pub fn delete(path: &Path) -> Result<(), TrashError> {
+        if path.symlink_metadata().is_err() {
+            return Err(TrashError::InvalidPath(path.display().to_string()));
+        }
+        platform::delete(path)
+    }
+
+    /// Moves every path to the trash, carrying on past the ones that fail.
+    pub fn delete_all(str_paths: Vec<String>) -> TrashReport {
+        let mut report = TrashReport::default();
+        for str_path in str_paths {
+            let result = Trash::delete(Path::new(&str_path));
+            report.push(str_path, result);
+        }
+        report
+    }
+
+    pub fn list() -> Result<Vec<TrashEntry>, TrashError> {
+        platform::list()
+    }
+
+    /// Restores items by id, listing the trash only once. Fails as a whole only
+    /// when the trash cannot be read.
+    pub fn restore(ids: &[String]) -> Result<TrashReport, TrashError> {
+        let results = platform::restore(ids)?;
+        let mut report = TrashReport::default();
+        for (id, result) in ids.iter().zip(results) {
+            report.push(id.clone(), result);
+        }
+        Ok(report)
+    }
+
+    pub fn empty() -> Result<(), TrashError> {
+        platform::empty()
+    }
+}
+
+#[cfg(any(
+    target_os = "windows",
+    all(
+        unix,
+        not(target_os = "macos"),
+        not(target_os = "ios"),
+        not(target_os = "android")
+    )
+))]
+mod platform {
+    use std::collections::HashMap;
+    use std::path::Path;
+
+    use trash::os_limited;
+
+    use super::{TrashEntry, TrashError};
+
+    impl From<trash::Error> for TrashError {
+        fn from(err: trash::Error) -> Self {
+            match err {
+                trash::Error::RestoreCollision { path, .. } => {
+                    TrashError::RestoreCollision(path.display().to_string())
+                }
+                err => TrashError::SystemError(err.to_string()),
+            }
+        }
+    }
+
+    pub fn delete(path: &Path) -> Result<(), TrashError> {
+        Ok(trash::delete(path)?)
+    }
+
+    pub fn list() -> Result<Vec<TrashEntry>, TrashError> {
+        let entries = os_limited::list()?
+            .into_iter()
+            .map(|item| TrashEntry {
+                id: item.id.to_string_lossy().into_owned(),
+                name: item.name.to_string_lossy().into_owned(),
+                original_path: item.original_path(),
+                time_deleted: item.time_deleted,
+            })
+            .collect();
+        Ok(entries)
+    }
+
+    pub fn restore(ids: &[String]) -> Result<Vec<Result<(), TrashError>>, TrashError> {
+        let mut items: HashMap<String, _> = os_limited::list()?
+            .into_iter()
+            .map(|item| (item.id.to_string_lossy().into_owned(), item))
+            .collect();
+        let results = ids
+            .iter()
+            .map(|id| {
+                let item = items
+                    .remove(id)
+                    .ok_or_else(|| TrashError::NotFound(id.clone()))?;
+                Ok(os_limited::restore_all([item])?)
+            })
+            .collect();
+        Ok(results)
+    }
+
+    pub fn empty() -> Result<(), TrashError> {
+        Ok(os_limited::purge_all(os_limited::list()?)?)
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::path::Path;
+
+    use super::{TrashEntry, TrashError};
+
+    impl From<trash::Error> for TrashError {
+        fn from(err: trash::Error) -> Self {
+            TrashError::SystemError(err.to_string())
+        }
+    }
+
+    pub fn delete(path: &Path) -> Result<(), TrashError> {
+        Ok(trash::delete(path)?)
+    }
+
+    pub fn list() -> Result<Vec<TrashEntry>, TrashError> {
+        Err(TrashError::Unsupported)
+    }
+
+    pub fn restore(_ids: &[String]) -> Result<Vec<Result<(), TrashError>>, TrashError> {
+        Err(TrashError::Unsupported)
+    }
+
+    pub fn empty() -> Result<(), TrashError> {
+        Err(TrashError::Unsupported)
+    }
+}
+
+#[cfg(any(target_os = "ios", target_os = "android"))]
+mod platform {
+    use std::path::Path;
+
+    use super::{TrashEntry, TrashError};
+
+    pub fn delete(_path: &Path) -> Result<(), TrashError> {
+        Err(TrashError::Unsupported)
+    }
+
+    pub fn list() -> Result<Vec<TrashEntry>, TrashError> {
+        Err(TrashError::Unsupported)
+    }
+
+    pub fn restore(_ids: &[String]) -> Result<Vec<Result<(), TrashError>>, TrashError> {
+        Err(TrashError::Unsupported)
+    }
+
+    pub fn empty() -> Result<(), TrashError> {
+        Err(TrashError::Unsupported)
+    }
+}
+
+/// Runs a trash call on the blocking pool, since large batches can take a while.
+async fn run_blocking<T, F>(call: F) -> Result<T, TrashError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, TrashError> + Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(call)
+        .await
+        .map_err(|err| TrashError::SystemError(err.to_string()))?
+}
+
+#[tauri::command]
+pub async fn move_to_trash(str_paths: Vec<String>) -> Result<TrashReport, TrashError> {
+    run_blocking(move || Ok(Trash::delete_all(str_paths))).await
+}
+
+#[tauri::command]
+pub async fn list_trash() -> Result<Vec<TrashEntry>, TrashError> {
+    run_blocking(Trash::list).await
+}
+
+#[tauri::command]
+pub async fn restore_from_trash(ids: Vec<String>) -> Result<TrashReport, TrashError> {
+    run_blocking(move || Trash::restore(&ids)).await
+}
+
+#[tauri::command]
+pub async fn empty_trash() -> Result<(), TrashError> {
+    run_blocking(Trash::empty).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_paths_fail_one_by_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = |name: &str| dir.path().join(name).display().to_string();
+        let report = Trash::delete_all(vec![missing("a"), missing("b")]);
+        assert!(report.done.is_empty());
+        assert_eq!(report.failures.len(), 2);
+        assert_eq!(report.failures[0].item, missing("a"));
+        assert_eq!(report.failures[1].item, missing("b"));
+        assert!(report
+            .failures
+            .iter()
+            .all(|failure| matches!(failure.reason, TrashError::InvalidPath(_))));
+    }
+
+    #[test]
+    fn report_keeps_successes_in_order_next_to_failures() {
+        let mut report = TrashReport::default();
+        report.push("a".to_string(), Ok(()));
+        report.push("b".to_string(), Err(TrashError::NotFound("b".to_string())));
+        report.push("c".to_string(), Ok(()));
+        assert_eq!(report.done, ["a", "c"]);
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.failures[0].item, "b");
+        assert!(matches!(report.failures[0].reason, TrashError::NotFound(_)));
+    }
+}
@@ -0,0 +1,941 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::fs::{self, File};
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime};
+use regex::{NoExpand, Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+pub enum RenameError {
+    InvalidPath(String),
+    InvalidRule(String),
+    Conflict(String),
+    IoError(String),
+}
+
+impl serde::Serialize for RenameError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let err_msg = match self {
+            RenameError::InvalidPath(path) => format!("Invalid Path: {}", path),
+            RenameError::InvalidRule(reason) => format!("Invalid Rule: {}", reason),
+            RenameError::Conflict(path) => format!("Conflict: {}", path),
+            RenameError::IoError(reason) => format!("IO Error: {}", reason),
+        };
+
+        serializer.serialize_str(err_msg.as_str())
+    }
+}
+
+impl From<io::Error> for RenameError {
+    fn from(err: io::Error) -> Self {
+        RenameError::IoError(err.to_string())
+    }
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CaseTransform {
+    Lower,
+    Upper,
+    /// Capitalizes the first letter of every word.
+    Title,
+    /// Capitalizes the first letter and lowercases the rest.
+    Sentence,
+}
+
+/// One step of a batch rename. Rules run in order, each on the result of the
+/// previous one, and only touch the name without its extension unless
+/// `include_extension` is set. Directories have no extension.
+#[derive(Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum RenameRule {
+    /// Replaces every match of `find`. With `regex`, `replace` may refer to
+    /// capture groups as `$1` or `${name}`.
+    #[serde(rename_all = "camelCase")]
+    Replace {
+        find: String,
+        replace: String,
+        #[serde(default)]
+        regex: bool,
+        #[serde(default)]
+        case_insensitive: bool,
+        #[serde(default)]
+        include_extension: bool,
+    },
+    /// Builds a new name from tokens:
+    /// - `{name}`, `{ext}` and `{parent}`: the current name, extension and
+    ///   containing directory name. A template that uses `{ext}` writes the
+    ///   whole name, so `{name}.{ext}` keeps `a.txt` as is.
+    /// - `{n}` or `{n:3}`: a counter over the file list, optionally zero-padded
+    /// - `{modified:%Y-%m-%d}` and `{created:...}`: file dates in local time
+    /// - `{taken:...}`: the EXIF capture date, or the modified date without one
+    /// - `{camera}`: the EXIF camera model, empty without one
+    ///
+    /// `{{` and `}}` are literal braces.
+    #[serde(rename_all = "camelCase")]
+    Template {
+        template: String,
+        #[serde(default = "default_counter_start")]
+        counter_start: u64,
+        #[serde(default = "default_counter_step")]
+        counter_step: u64,
+        #[serde(default)]
+        include_extension: bool,
+    },
+    #[serde(rename_all = "camelCase")]
+    Case {
+        case: CaseTransform,
+        #[serde(default)]
+        include_extension: bool,
+    },
+}
+
+fn default_counter_start() -> u64 {
+    1
+}
+
+fn default_counter_step() -> u64 {
+    1
+}
+
+/// Why a planned rename cannot be applied.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RenameConflict {
+    EmptyName,
+    /// The name contains a separator or a character the platform rejects.
+    InvalidName,
+    /// Another file in the batch is renamed to the same name.
+    Duplicate,
+    /// A file outside the batch already has the name.
+    Exists,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedRename {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    pub conflict: Option<RenameConflict>,
+}
+
+impl PlannedRename {
+    pub fn changed(&self) -> bool {
+        self.from != self.to
+    }
+}
+
+/// A preview of a batch rename: nothing has been touched until it is passed to
+/// `apply`, which refuses plans that still contain conflicts.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenamePlan {
+    pub renames: Vec<PlannedRename>,
+    pub conflicts: usize,
+}
+
+enum Token {
+    Literal(String),
+    Name,
+    Ext,
+    Parent,
+    Counter { width: usize },
+    Modified(String),
+    Created(String),
+    Taken(String),
+    Camera,
+}
+
+impl Token {
+    fn needs_metadata(&self) -> bool {
+        matches!(
+            self,
+            Token::Modified(_) | Token::Created(_) | Token::Taken(_) | Token::Camera
+        )
+    }
+
+    fn needs_exif(&self) -> bool {
+        matches!(self, Token::Taken(_) | Token::Camera)
+    }
+}
+
+enum CompiledRule {
+    Replace {
+        regex: Regex,
+        replace: String,
+        expand: bool,
+        include_extension: bool,
+    },
+    Template {
+        tokens: Vec<Token>,
+        counter_start: u64,
+        counter_step: u64,
+        include_extension: bool,
+    },
+    Case {
+        case: CaseTransform,
+        include_extension: bool,
+    },
+}
+
+impl CompiledRule {
+    fn include_extension(&self) -> bool {
+        match self {
+            CompiledRule::Replace {
+                include_extension, ..
+            }
+            | CompiledRule::Template {
+                include_extension, ..
+            }
+            | CompiledRule::Case {
+                include_extension, ..
+            } => *include_extension,
+        }
+    }
+
+    /// Whether the rule builds the extension itself, in which case the
+    /// original one is not appended again.
+    fn writes_extension(&self) -> bool {
+        match self {
+            CompiledRule::Template { tokens, .. } => {
+                tokens.iter().any(|token| matches!(token, Token::Ext))
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Dates and EXIF fields for the template tokens, read once per file and only
+/// when a template asks for them.
+#[derive(Default)]
+struct FileFacts {
+    modified: Option<SystemTime>,
+    created: Option<SystemTime>,
+    taken: Option<NaiveDateTime>,
+    camera: Option<String>,
+}
+
+pub struct BatchRenamer {
+    rules: Vec<CompiledRule>,
+}
+
+impl BatchRenamer {
+    pub fn new(rules: &[RenameRule]) -> Result<Self, RenameError> {
+        let rules = rules
+            .iter()
+            .map(compile_rule)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { rules })
+    }
+
+    /// Computes the new name of every path and flags the renames that would
+    /// collide with each other or with files already on disk.
+    pub fn plan(&self, paths: &[PathBuf]) -> Result<RenamePlan, RenameError> {
+        let needs_metadata = self.tokens().any(Token::needs_metadata);
+        let needs_exif = self.tokens().any(Token::needs_exif);
+
+        let mut renames = Vec::with_capacity(paths.len());
+        for (index, path) in paths.iter().enumerate() {
+            let metadata = fs::symlink_metadata(path)
+                .map_err(|_| RenameError::InvalidPath(path.display().to_string()))?;
+            let name = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .ok_or_else(|| RenameError::InvalidPath(path.display().to_string()))?;
+            let parent = path
+                .parent()
+                .ok_or_else(|| RenameError::InvalidPath(path.display().to_string()))?;
+
+            let facts = if needs_metadata {
+                read_facts(path, &metadata, needs_exif)
+            } else {
+                FileFacts::default()
+            };
+            let new_name = self.rename(name, metadata.is_dir(), parent, index as u64, &facts);
+            let conflict = check_name(&new_name);
+            renames.push(PlannedRename {
+                from: path.clone(),
+                to: parent.join(&new_name),
+                conflict,
+            });
+        }
+
+        mark_collisions(&mut renames);
+        let conflicts = renames
+            .iter()
+            .filter(|planned| planned.conflict.is_some())
+            .count();
+        Ok(RenamePlan { renames, conflicts })
+    }
+
+    fn tokens(&self) -> impl Iterator<Item = &Token> {
+        self.rules.iter().flat_map(|rule| match rule {
+            CompiledRule::Template { tokens, .. } => tokens.as_slice(),
+            _ => &[],
+        })
+    }
+
+    fn rename(
+        &self,
+        name: &str,
+        is_dir: bool,
+        parent: &Path,
+        index: u64,
+        facts: &FileFacts,
+    ) -> String {
+        let mut name = name.to_string();
+        for rule in &self.rules {
+            let (stem, ext) = match split_extension(&name, is_dir) {
+                (stem, Some(ext)) if !rule.include_extension() => {
+                    (stem.to_string(), Some(ext.to_string()))
+                }
+                (_, ext) => (name.clone(), ext.map(str::to_string)),
+            };
+            let suffix = ext
+                .as_deref()
+                .filter(|_| !rule.include_extension() && !rule.writes_extension());
+
+            let renamed = match rule {
+                CompiledRule::Replace {
+                    regex,
+                    replace,
+                    expand,
+                    ..
+                } => {
+                    if *expand {
+                        regex.replace_all(&stem, replace.as_str()).into_owned()
+                    } else {
+                        regex.replace_all(&stem, NoExpand(replace)).into_owned()
+                    }
+                }
+                CompiledRule::Template {
+                    tokens,
+                    counter_start,
+                    counter_step,
+                    ..
+                } => {
+                    let counter = counter_start.saturating_add(index.saturating_mul(*counter_step));
+                    let context = TemplateContext {
+                        stem: &stem,
+                        ext: ext.as_deref(),
+                        parent,
+                        counter,
+                        facts,
+                    };
+                    render(tokens, &context)
+                }
+                CompiledRule::Case { case, .. } => transform_case(&stem, *case),
+            };
+
+            name = match suffix {
+                Some(ext) => format!("{}.{}", renamed, ext),
+                None => renamed,
+            };
+        }
+        name
+    }
+}
+
+fn compile_rule(rule: &RenameRule) -> Result<CompiledRule, RenameError> {
+    match rule {
+        RenameRule::Replace {
+            find,
+            replace,
+            regex,
+            case_insensitive,
+            include_extension,
+        } => {
+            if find.is_empty() {
+                return Err(RenameError::InvalidRule("empty search text".to_string()));
+            }
+            let pattern = if *regex {
+                find.clone()
+            } else {
+                regex::escape(find)
+            };
+            let compiled = RegexBuilder::new(&pattern)
+                .case_insensitive(*case_insensitive)
+                .build()
+                .map_err(|err| RenameError::InvalidRule(err.to_string()))?;
+            Ok(CompiledRule::Replace {
+                regex: compiled,
+                replace: replace.clone(),
+                expand: *regex,
+                include_extension: *include_extension,
+            })
+        }
+        RenameRule::Template {
+            template,
+            counter_start,
+            counter_step,
+            include_extension,
+        } => Ok(CompiledRule::Template {
+            tokens: parse_template(template)?,
+            counter_start: *counter_start,
+            counter_step: *counter_step,
+            include_extension: *include_extension,
+        }),
+        RenameRule::Case {
+            case,
+            include_extension,
+        } => Ok(CompiledRule::Case {
+            case: *case,
+            include_extension: *include_extension,
+        }),
+    }
+}
+
+fn parse_template(template: &str) -> Result<Vec<Token>, RenameError> {
+    let mut tokens = Vec::new();
+    let mut literal = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                literal.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                literal.push('}');
+            }
+            '{' => {
+                let mut spec = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => spec.push(c),
+                        None => {
+                            return Err(RenameError::InvalidRule(format!(
+                                "unclosed token in \"{}\"",
+                                template
+                            )))
+                        }
+                    }
+                }
+                if !literal.is_empty() {
+                    tokens.push(Token::Literal(std::mem::take(&mut literal)));
+                }
+                tokens.push(parse_token(&spec)?);
+            }
+            '}' => {
+                return Err(RenameError::InvalidRule(format!(
+                    "unmatched '}}' in \"{}\"",
+                    template
+                )))
+            }
+            c => literal.push(c),
+        }
+    }
+    if !literal.is_empty() {
+        tokens.push(Token::Literal(literal));
+    }
+    Ok(tokens)
+}
+
+fn parse_token(spec: &str) -> Result<Token, RenameError> {
+    let (key, arg) = match spec.split_once(':') {
+        Some((key, arg)) => (key, Some(arg)),
+        None => (spec, None),
+    };
+    let date_format = |arg: Option<&str>| -> Result<String, RenameError> {
+        let format = arg.unwrap_or("%Y-%m-%d");
+        if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+            return Err(RenameError::InvalidRule(format!(
+                "invalid date format \"{}\"",
+                format
+            )));
+        }
+        Ok(format.to_string())
+    };
+
+    match (key, arg) {
+        ("name", None) => Ok(Token::Name),
+        ("ext", None) => Ok(Token::Ext),
+        ("parent", None) => Ok(Token::Parent),
+        ("camera", None) => Ok(Token::Camera),
+        ("n", None) => Ok(Token::Counter { width: 0 }),
+        ("n", Some(width)) => width
+            .parse()
+            .map(|width| Token::Counter { width })
+            .map_err(|_| RenameError::InvalidRule(format!("invalid counter width \"{}\"", width))),
+        ("modified", arg) => Ok(Token::Modified(date_format(arg)?)),
+        ("created", arg) => Ok(Token::Created(date_format(arg)?)),
+        ("taken", arg) => {
+            // EXIF dates carry no offset, so `%z` and friends cannot be formatted.
+            let format = date_format(arg)?;
+            let sample = NaiveDateTime::default();
+            if write!(String::new(), "{}", sample.format(&format)).is_err() {
+                return Err(RenameError::InvalidRule(format!(
+                    "date format \"{}\" needs a time zone, which photo dates lack",
+                    format
+                )));
+            }
+            Ok(Token::Taken(format))
+        }
+        _ => Err(RenameError::InvalidRule(format!(
+            "unknown token {{{}}}",
+            spec
+        ))),
+    }
+}
+
+struct TemplateContext<'a> {
+    stem: &'a str,
+    /// `None` when the file has no extension, as opposed to a trailing dot.
+    ext: Option<&'a str>,
+    parent: &'a Path,
+    counter: u64,
+    facts: &'a FileFacts,
+}
+
+fn render(tokens: &[Token], context: &TemplateContext) -> String {
+    let local = |time: Option<SystemTime>, format: &str| {
+        time.map(|time| DateTime::<Local>::from(time).format(format).to_string())
+            .unwrap_or_default()
+    };
+
+    let mut out = String::new();
+    let mut after_literal = false;
+    for token in tokens {
+        match token {
+            Token::Literal(text) => out.push_str(text),
+            Token::Name => out.push_str(context.stem),
+            Token::Ext => match context.ext {
+                Some(ext) => out.push_str(ext),
+                // `{name}.{ext}` on a file without an extension keeps the bare
+                // name rather than leaving a trailing dot.
+                None if after_literal && out.ends_with('.') => {
+                    out.pop();
+                }
+                None => {}
+            },
+            Token::Parent => {
+                if let Some(parent) = context.parent.file_name() {
+                    out.push_str(&parent.to_string_lossy());
+                }
+            }
+            Token::Counter { width } => {
+                out.push_str(&format!("{:0width$}", context.counter, width = *width))
+            }
+            Token::Modified(format) => out.push_str(&local(context.facts.modified, format)),
+            Token::Created(format) => out.push_str(&local(context.facts.created, format)),
+            Token::Taken(format) => match context.facts.taken {
+                // Formats are checked in parse_token, but an error must never panic.
+                Some(taken) => {
+                    let _ = write!(out, "{}", taken.format(format));
+                }
+                None => out.push_str(&local(context.facts.modified, format)),
+            },
+            Token::Camera => {
+                if let Some(camera) = &context.facts.camera {
+                    out.push_str(camera);
+                }
+            }
+        }
+        after_literal = matches!(token, Token::Literal(_));
+    }
+    out
+}
+
+fn read_facts(path: &Path, metadata: &fs::Metadata, read_exif: bool) -> FileFacts {
+    let mut facts = FileFacts {
+        modified: metadata.modified().ok(),
+        created: metadata.created().ok(),
+        ..Default::default()
+    };
+    if !read_exif || !metadata.is_file() {
+        return facts;
+    }
+
+    let Ok(file) = File::open(path) else {
+        return facts;
+    };
+    let Ok(exif) = exif::Reader::new().read_from_container(&mut BufReader::new(file)) else {
+        return facts;
+    };
+    let ascii = |tag: exif::Tag| match exif.get_field(tag, exif::In::PRIMARY) {
+        Some(exif::Field {
+            value: exif::Value::Ascii(values),
+            ..
+        }) => values.first().cloned(),
+        _ => None,
+    };
+
+    facts.taken = ascii(exif::Tag::DateTimeOriginal)
+        .or_else(|| ascii(exif::Tag::DateTime))
+        .and_then(|bytes| exif::DateTime::from_ascii(&bytes).ok())
+        .and_then(|taken| {
+            NaiveDate::from_ymd_opt(taken.year.into(), taken.month.into(), taken.day.into())?
+                .and_hms_opt(taken.hour.into(), taken.minute.into(), taken.second.into())
+        });
+    facts.camera = ascii(exif::Tag::Model)
+        .map(|bytes| String::from_utf8_lossy(&bytes).trim().to_string())
+        .filter(|camera| !camera.is_empty());
+    facts
+}
+
+/// Splits "archive.tar.gz" into "archive.tar" and "gz". Dotfiles such as
+/// ".bashrc" and directories have no extension.
+fn split_extension(name: &str, is_dir: bool) -> (&str, Option<&str>) {
+    if is_dir {
+        return (name, None);
+    }
+    match name.rfind('.') {
+        Some(dot) if dot > 0 => (&name[..dot], Some(&name[dot + 1..])),
+        _ => (name, None),
+    }
+}
+
+fn transform_case(text: &str, case: CaseTransform) -> String {
+    match case {
+        CaseTransform::Lower => text.to_lowercase(),
+        CaseTransform::Upper => text.to_uppercase(),
+        CaseTransform::Title => {
+            let mut out = String::with_capacity(text.len());
+            let mut word_start = true;
+            for c in text.chars() {
+                if word_start {
+                    out.extend(c.to_uppercase());
+                } else {
+                    out.extend(c.to_lowercase());
+                }
+                word_start = !c.is_alphanumeric() && c != '\'';
+            }
+            out
+        }
+        CaseTransform::Sentence => {
+            let mut chars = text.chars();
+            match chars.next() {
+                Some(first) => first
+                    .to_uppercase()
+                    .chain(chars.flat_map(char::to_lowercase))
+                    .collect(),
+                None => String::new(),
+            }
+        }
+    }
+}
+
+fn check_name(name: &str) -> Option<RenameConflict> {
+    if name.is_empty() || name == "." || name == ".." {
+        return Some(RenameConflict::EmptyName);
+    }
+    if name.contains(['/', '\0']) || !is_valid_platform_name(name) {
+        return Some(RenameConflict::InvalidName);
+    }
+    None
+}
+
+#[cfg(windows)]
+fn is_valid_platform_name(name: &str) -> bool {
+    const RESERVED: &[&str] = &[
+        "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+        "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+    ];
+    let stem = name.split('.').next().unwrap_or(name);
+    !name.contains(['\\', '<', '>', ':', '"', '|', '?', '*'])
+        && !name.chars().any(char::is_control)
+        && !name.ends_with(['.', ' '])
+        && !RESERVED
+            .iter()
+            .any(|reserved| stem.eq_ignore_ascii_case(reserved))
+}
+
+#[cfg(not(windows))]
+fn is_valid_platform_name(_name: &str) -> bool {
+    true
+}
+
+/// How the file system compares names: the default Windows and macOS volumes
+/// ignore case.
+fn path_key(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    if cfg!(any(windows, target_os = "macos")) {
+        path.to_lowercase()
+    } else {
+        path.into_owned()
+    }
+}
+
+fn mark_collisions(renames: &mut [PlannedRename]) {
+    let mut targets: HashMap<String, usize> = HashMap::new();
+    for planned in renames.iter() {
+        *targets.entry(path_key(&planned.to)).or_default() += 1;
+    }
+    let sources: HashSet<String> = renames
+        .iter()
+        .map(|planned| path_key(&planned.from))
+        .collect();
+
+    for planned in renames.iter_mut() {
+        if planned.conflict.is_some() || !planned.changed() {
+            continue;
+        }
+        let key = path_key(&planned.to);
+        if targets[&key] > 1 {
+            planned.conflict = Some(RenameConflict::Duplicate);
+        } else if !sources.contains(&key) && fs::symlink_metadata(&planned.to).is_ok() {
+            planned.conflict = Some(RenameConflict::Exists);
+        }
+    }
+}
+
+/// Applies a plan as a whole. Every file is first moved to a temporary name so
+/// that swaps and case-only renames work, then to its target; if any step
+/// fails, the files already renamed are put back.
+pub fn apply(plan: &RenamePlan) -> Result<Vec<PlannedRename>, RenameError> {
+    if let Some(planned) = plan
+        .renames
+        .iter()
+        .find(|planned| planned.conflict.is_some())
+    {
+        return Err(RenameError::Conflict(planned.to.display().to_string()));
+    }
+
+    let renames: Vec<&PlannedRename> = plan
+        .renames
+        .iter()
+        .filter(|planned| planned.changed())
+        .collect();
+    let sources: HashSet<String> = renames
+        .iter()
+        .map(|planned| path_key(&planned.from))
+        .collect();
+    for planned in &renames {
+        if fs::symlink_metadata(&planned.from).is_err() {
+            return Err(RenameError::InvalidPath(planned.from.display().to_string()));
+        }
+        if !sources.contains(&path_key(&planned.to)) && fs::symlink_metadata(&planned.to).is_ok() {
+            return Err(RenameError::Conflict(planned.to.display().to_string()));
+        }
+    }
+
+    let mut staged: Vec<PathBuf> = Vec::with_capacity(renames.len());
+    for planned in &renames {
+        let result = temp_path(&planned.from)
+            .and_then(|temp| fs::rename(&planned.from, &temp).map(|()| temp));
+        match result {
+            Ok(temp) => staged.push(temp),
+            Err(err) => {
+                roll_back(&renames, &staged, 0);
+                return Err(err.into());
+            }
+        }
+    }
+
+    for (done, planned) in renames.iter().enumerate() {
+        if let Err(err) = fs::rename(&staged[done], &planned.to) {
+            roll_back(&renames, &staged, done);
+            return Err(err.into());
+        }
+    }
+
+    Ok(renames.into_iter().cloned().collect())
+}
+
+/// Moves the first `finished` files back from their targets to their temporary
+/// names, then every staged file back to its original name.
+fn roll_back(renames: &[&PlannedRename], staged: &[PathBuf], finished: usize) {
+    for (planned, temp) in renames.iter().zip(staged).take(finished).rev() {
+        let _ = fs::rename(&planned.to, temp);
+    }
+    for (planned, temp) in renames.iter().zip(staged).rev() {
+        let _ = fs::rename(temp, &planned.from);
+    }
+}
+
+/// A free hidden name next to `path`.
+fn temp_path(path: &Path) -> io::Result<PathBuf> {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    (0..)
+        .map(|attempt| path.with_file_name(format!(".{}.nimbus-rename-{}", name, attempt)))
+        .find(|candidate| fs::symlink_metadata(candidate).is_err())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::AlreadyExists, "no free temporary name"))
+}
+
+#[tauri::command]
+pub async fn plan_batch_rename(
+    str_paths: Vec<String>,
+    rules: Vec<RenameRule>,
+) -> Result<RenamePlan, RenameError> {
+    let renamer = BatchRenamer::new(&rules)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let paths: Vec<PathBuf> = str_paths.into_iter().map(PathBuf::from).collect();
+        renamer.plan(&paths)
+    })
+    .await
+    .map_err(|err| RenameError::IoError(err.to_string()))?
+}
+
+#[tauri::command]
+pub async fn apply_batch_rename(plan: RenamePlan) -> Result<Vec<PlannedRename>, RenameError> {
+    tauri::async_runtime::spawn_blocking(move || apply(&plan))
+        .await
+        .map_err(|err| RenameError::IoError(err.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(template: &str, include_extension: bool) -> RenameRule {
+        RenameRule::Template {
+            template: template.to_string(),
+            counter_start: 1,
+            counter_step: 1,
+            include_extension,
+        }
+    }
+
+    fn renamed(rules: &[RenameRule], name: &str) -> String {
+        BatchRenamer::new(rules).ok().unwrap().rename(
+            name,
+            false,
+            Path::new("photos"),
+            0,
+            &FileFacts::default(),
+        )
+    }
+
+    fn planned(from: &Path, to: &Path) -> PlannedRename {
+        PlannedRename {
+            from: from.to_path_buf(),
+            to: to.to_path_buf(),
+            conflict: None,
+        }
+    }
+
+    #[test]
+    fn parse_template_splits_tokens_and_literals() {
+        let tokens = parse_template("{{{name}}}-{n:3}").ok().unwrap();
+        assert_eq!(tokens.len(), 4);
+        assert!(matches!(&tokens[0], Token::Literal(text) if text == "{"));
+        assert!(matches!(tokens[1], Token::Name));
+        assert!(matches!(&tokens[2], Token::Literal(text) if text == "}-"));
+        assert!(matches!(tokens[3], Token::Counter { width: 3 }));
+    }
+
+    #[test]
+    fn parse_template_rejects_unbalanced_braces() {
+        assert!(matches!(
+            parse_template("{name"),
+            Err(RenameError::InvalidRule(_))
+        ));
+        assert!(matches!(
+            parse_template("name}"),
+            Err(RenameError::InvalidRule(_))
+        ));
+        assert!(matches!(
+            parse_template("{size}"),
+            Err(RenameError::InvalidRule(_))
+        ));
+    }
+
+    #[test]
+    fn split_extension_keeps_dotfiles_and_directories_whole() {
+        assert_eq!(split_extension("a.tar.gz", false), ("a.tar", Some("gz")));
+        assert_eq!(split_extension(".bashrc", false), (".bashrc", None));
+        assert_eq!(split_extension("notes", false), ("notes", None));
+        assert_eq!(split_extension("v1.2", true), ("v1.2", None));
+    }
+
+    #[test]
+    fn template_with_ext_token_does_not_repeat_extension() {
+        assert_eq!(
+            renamed(&[template("{name}.{ext}", false)], "a.txt"),
+            "a.txt"
+        );
+        assert_eq!(
+            renamed(&[template("{parent}_{n:2}.{ext}", false)], "a.txt"),
+            "photos_01.txt"
+        );
+        assert_eq!(renamed(&[template("{name}_x", false)], "a.txt"), "a_x.txt");
+        assert_eq!(renamed(&[template("{name}_x", true)], "a.txt"), "a.txt_x");
+    }
+
+    #[test]
+    fn template_with_ext_token_leaves_no_trailing_dot() {
+        let rules = [template("{name}.{ext}", false)];
+        assert_eq!(renamed(&rules, "README"), "README");
+        assert_eq!(renamed(&rules, "notes."), "notes.");
+        assert_eq!(
+            renamed(&[template("{name}-{ext}", false)], "README"),
+            "README-"
+        );
+    }
+
+    #[test]
+    fn taken_rejects_formats_that_need_an_offset() {
+        for format in ["{taken:%z}", "{taken:%Z}", "{taken:%Y %:z}"] {
+            assert!(matches!(
+                parse_template(format),
+                Err(RenameError::InvalidRule(_))
+            ));
+        }
+        assert!(parse_template("{taken:%Y-%m-%d %H%M}").is_ok());
+        assert!(parse_template("{modified:%z}").is_ok());
+    }
+
+    #[test]
+    fn mark_collisions_flags_duplicates_and_existing_files() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["a", "b", "c", "taken"] {
+            fs::write(dir.path().join(name), name).unwrap();
+        }
+        let mut renames = vec![
+            planned(&dir.path().join("a"), &dir.path().join("same")),
+            planned(&dir.path().join("b"), &dir.path().join("same")),
+            planned(&dir.path().join("c"), &dir.path().join("taken")),
+        ];
+        mark_collisions(&mut renames);
+        assert!(renames[0].conflict == Some(RenameConflict::Duplicate));
+        assert!(renames[1].conflict == Some(RenameConflict::Duplicate));
+        assert!(renames[2].conflict == Some(RenameConflict::Exists));
+    }
+
+    #[test]
+    fn apply_swaps_names_within_the_batch() {
+        let dir = tempfile::tempdir().unwrap();
+        let (a, b) = (dir.path().join("a"), dir.path().join("b"));
+        fs::write(&a, "first").unwrap();
+        fs::write(&b, "second").unwrap();
+
+        let mut renames = vec![planned(&a, &b), planned(&b, &a)];
+        mark_collisions(&mut renames);
+        let plan = RenamePlan {
+            renames,
+            conflicts: 0,
+        };
+        assert_eq!(apply(&plan).ok().unwrap().len(), 2);
+        assert_eq!(fs::read_to_string(&a).unwrap(), "second");
+        assert_eq!(fs::read_to_string(&b).unwrap(), "first");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn apply_refuses_plans_with_conflicts() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a");
+        fs::write(&a, "first").unwrap();
+        let plan = RenamePlan {
+            renames: vec![PlannedRename {
+                conflict: Some(RenameConflict::Exists),
+                ..planned(&a, &dir.path().join("b"))
+            }],
+            conflicts: 1,
+        };
+        assert!(matches!(apply(&plan), Err(RenameError::Conflict(_))));
+        assert!(a.exists());
+    }
+}
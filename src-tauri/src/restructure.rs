@@ -0,0 +1,795 @@
+use std::collections::{BTreeSet, HashSet};
+use std::fs;
+use std::io;
+use std::ops::Bound;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+use crate::file_ops::unique_name;
+
+const JOURNAL_FILE: &str = "restructure-journal.json";
+const JOURNAL_LIMIT: usize = 50;
+
+pub enum RestructureError {
+    InvalidPath(String),
+    InvalidMapping { line: usize, reason: String },
+    Conflict(String),
+    NotInJournal(u64),
+    IoError(String),
+}
+
+impl serde::Serialize for RestructureError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let err_msg = match self {
+            RestructureError::InvalidPath(path) => format!("Invalid Path: {}", path),
+            RestructureError::InvalidMapping { line, reason } => {
+                format!("Invalid Mapping (line {}): {}", line, reason)
+            }
+            RestructureError::Conflict(path) => format!("Already Exists: {}", path),
+            RestructureError::NotInJournal(id) => format!("Not In Undo Journal: {}", id),
+            RestructureError::IoError(reason) => format!("IO Error: {}", reason),
+        };
+
+        serializer.serialize_str(err_msg.as_str())
+    }
+}
+
+impl From<io::Error> for RestructureError {
+    fn from(err: io::Error) -> Self {
+        RestructureError::IoError(err.to_string())
+    }
+}
+
+impl From<serde_json::Error> for RestructureError {
+    fn from(err: serde_json::Error) -> Self {
+        RestructureError::IoError(err.to_string())
+    }
+}
+
+impl From<tauri::Error> for RestructureError {
+    fn from(err: tauri::Error) -> Self {
+        RestructureError::IoError(err.to_string())
+    }
+}
+
+/// What to do when two files would end up with the same name.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CollisionPolicy {
+    /// Give the later file a `name (2).ext` style name.
+    #[default]
+    Rename,
+    /// Leave the later file where it is.
+    Skip,
+    Fail,
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SplitKey {
+    /// `YYYY-MM` of the modification time, in local time.
+    Month,
+    Extension,
+    InitialLetter,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedMove {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+/// Why a move cannot be applied, whatever the collision policy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MoveConflict {
+    /// A folder on the way to the target is a file, or the target of another move.
+    ParentIsFile,
+    /// The source is inside another source, contains one, or would end up
+    /// inside one.
+    NestedSource,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictingMove {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    pub reason: MoveConflict,
+}
+
+/// A dry-run result: nothing has been touched until it is passed to `apply`,
+/// which refuses plans with conflicts.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestructurePlan {
+    pub description: String,
+    pub root: PathBuf,
+    pub moves: Vec<PlannedMove>,
+    /// Files left in place because of `CollisionPolicy::Skip`.
+    pub skipped: Vec<PathBuf>,
+    /// Moves left out of `moves` because they could not succeed.
+    pub conflicts: Vec<ConflictingMove>,
+    /// Remove the directories under `root` that the moves leave empty.
+    pub remove_empty_dirs: bool,
+}
+
+/// Everything needed to revert an applied plan.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalEntry {
+    pub id: u64,
+    pub description: String,
+    pub moves: Vec<PlannedMove>,
+    /// Directories created for the moves, parents before children.
+    pub created_dirs: Vec<PathBuf>,
+    /// Empty directories removed afterwards, children before parents.
+    pub removed_dirs: Vec<PathBuf>,
+}
+
+/// Moves every file below `root` into `root` itself.
+pub fn plan_flatten(
+    root: &Path,
+    collision: CollisionPolicy,
+) -> Result<RestructurePlan, RestructureError> {
+    ensure_dir(root)?;
+
+    let mut files = Vec::new();
+    for entry in fs::read_dir(root)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            collect_files(&entry.path(), &mut files)?;
+        }
+    }
+    files.sort();
+
+    let mut planner = Planner::new(root, collision);
+    for file in files {
+        let Some(name) = file.file_name() else {
+            continue;
+        };
+        let target = root.join(name);
+        planner.add(file, target)?;
+    }
+
+    Ok(planner.finish(format!("Flatten {}", root.display()), true))
+}
+
+/// Sorts the files directly inside `dir` into subfolders named by `key`.
+pub fn plan_split(dir: &Path, key: SplitKey) -> Result<RestructurePlan, RestructureError> {
+    ensure_dir(dir)?;
+
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if !metadata.is_dir() {
+            files.push((entry.path(), metadata));
+        }
+    }
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut planner = Planner::new(dir, CollisionPolicy::Rename);
+    for (file, metadata) in files {
+        let Some(name) = file.file_name() else {
+            continue;
+        };
+        let folder = split_folder(&file, &metadata, key);
+        let target = dir.join(folder).join(name);
+        planner.add(file, target)?;
+    }
+
+    Ok(planner.finish(format!("Split {}", dir.display()), false))
+}
+
+/// Builds moves from a mapping file with one `source<TAB>target` pair per line,
+/// both relative to `root`. Blank lines and lines starting with `#` are ignored.
+pub fn plan_from_mapping(
+    root: &Path,
+    mapping: &str,
+    collision: CollisionPolicy,
+) -> Result<RestructurePlan, RestructureError> {
+    ensure_dir(root)?;
+
+    let mut planner = Planner::new(root, collision);
+    for (index, line) in mapping.lines().enumerate() {
+        let line_no = index + 1;
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let invalid = |reason: &str| RestructureError::InvalidMapping {
+            line: line_no,
+            reason: reason.to_string(),
+        };
+        let (source, target) = line
+            .split_once('\t')
+            .ok_or_else(|| invalid("expected source and target separated by a tab"))?;
+        let source = relative_to(root, source).ok_or_else(|| invalid("source escapes root"))?;
+        let target = relative_to(root, target).ok_or_else(|| invalid("target escapes root"))?;
+        if fs::symlink_metadata(&source).is_err() {
+            return Err(invalid("source does not exist"));
+        }
+        planner.add(source, target)?;
+    }
+
+    Ok(planner.finish(format!("Restructure {}", root.display()), false))
+}
+
+/// Applies a plan, rolling back the moves already made if one fails.
+pub fn apply(plan: &RestructurePlan) -> Result<JournalEntry, RestructureError> {
+    if let Some(conflict) = plan.conflicts.first() {
+        return Err(RestructureError::Conflict(
+            conflict.to.display().to_string(),
+        ));
+    }
+    for planned in &plan.moves {
+        if fs::symlink_metadata(&planned.from).is_err() {
+            return Err(RestructureError::InvalidPath(
+                planned.from.display().to_string(),
+            ));
+        }
+        if fs::symlink_metadata(&planned.to).is_ok() {
+            return Err(RestructureError::Conflict(planned.to.display().to_string()));
+        }
+    }
+
+    let mut done: Vec<PlannedMove> = Vec::with_capacity(plan.moves.len());
+    let mut created_dirs = Vec::new();
+    for planned in &plan.moves {
+        let result = match planned.to.parent() {
+            Some(parent) => create_missing_dirs(parent, &mut created_dirs),
+            None => Ok(()),
+        }
+        .and_then(|()| fs::rename(&planned.from, &planned.to));
+
+        if let Err(err) = result {
+            revert_moves(&done, &created_dirs);
+            return Err(err.into());
+        }
+        done.push(planned.clone());
+    }
+
+    // The moves are done and must stay undoable, so cleanup is best effort.
+    let mut removed_dirs = Vec::new();
+    if plan.remove_empty_dirs {
+        remove_emptied_dirs(&plan.root, &done, &mut removed_dirs);
+    }
+
+    Ok(JournalEntry {
+        id: next_journal_id(),
+        description: plan.description.clone(),
+        moves: done,
+        created_dirs,
+        removed_dirs,
+    })
+}
+
+/// The current time in milliseconds, bumped past the previous id so that plans
+/// applied within the same millisecond do not share one.
+fn next_journal_id() -> u64 {
+    static LAST_ID: AtomicU64 = AtomicU64::new(0);
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default();
+    let previous = LAST_ID
+        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |last| {
+            Some(now.max(last + 1))
+        })
+        .unwrap_or_default();
+    now.max(previous + 1)
+}
+
+/// Reverts an applied plan. Moves that are already reverted are skipped, so an
+/// undo interrupted by a conflict can be retried.
+pub fn undo(entry: &JournalEntry) -> Result<(), RestructureError> {
+    for dir in entry.removed_dirs.iter().rev() {
+        fs::create_dir_all(dir)?;
+    }
+    for planned in entry.moves.iter().rev() {
+        let from_exists = fs::symlink_metadata(&planned.from).is_ok();
+        let to_exists = fs::symlink_metadata(&planned.to).is_ok();
+        if from_exists && !to_exists {
+            continue;
+        }
+        if from_exists {
+            return Err(RestructureError::Conflict(
+                planned.from.display().to_string(),
+            ));
+        }
+        if let Some(parent) = planned.from.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(&planned.to, &planned.from)?;
+    }
+    for dir in entry.created_dirs.iter().rev() {
+        let _ = fs::remove_dir(dir);
+    }
+    Ok(())
+}
+
+/// Serializes journal updates, which read and rewrite the whole file, now that
+/// plans can be applied from several commands at once.
+static JOURNAL_LOCK: Mutex<()> = Mutex::new(());
+
+/// Applied plans, newest last, stored as JSON in the app data directory.
+pub struct UndoJournal {
+    path: PathBuf,
+}
+
+impl UndoJournal {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn entries(&self) -> Result<Vec<JournalEntry>, RestructureError> {
+        match fs::read(&self.path) {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn push(&self, entry: JournalEntry) -> Result<(), RestructureError> {
+        let _guard = JOURNAL_LOCK.lock().unwrap();
+        let mut entries = self.entries()?;
+        entries.push(entry);
+        let excess = entries.len().saturating_sub(JOURNAL_LIMIT);
+        entries.drain(..excess);
+        self.save(&entries)
+    }
+
+    pub fn take(&self, id: u64) -> Result<JournalEntry, RestructureError> {
+        let _guard = JOURNAL_LOCK.lock().unwrap();
+        let mut entries = self.entries()?;
+        let index = entries
+            .iter()
+            .position(|entry| entry.id == id)
+            .ok_or(RestructureError::NotInJournal(id))?;
+        let entry = entries.remove(index);
+        self.save(&entries)?;
+        Ok(entry)
+    }
+
+    fn save(&self, entries: &[JournalEntry]) -> Result<(), RestructureError> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_vec(entries)?)?;
+        Ok(())
+    }
+}
+
+/// Collects planned moves while keeping targets unique.
+struct Planner<'a> {
+    root: &'a Path,
+    collision: CollisionPolicy,
+    moves: Vec<PlannedMove>,
+    skipped: Vec<PathBuf>,
+    conflicts: Vec<ConflictingMove>,
+    claimed: HashSet<PathBuf>,
+    /// Sources and targets in path order, for finding nested paths.
+    sources: BTreeSet<PathBuf>,
+    targets: BTreeSet<PathBuf>,
+}
+
+impl<'a> Planner<'a> {
+    fn new(root: &'a Path, collision: CollisionPolicy) -> Self {
+        Self {
+            root,
+            collision,
+            moves: Vec::new(),
+            skipped: Vec::new(),
+            conflicts: Vec::new(),
+            claimed: HashSet::new(),
+            sources: BTreeSet::new(),
+            targets: BTreeSet::new(),
+        }
+    }
+
+    fn add(&mut self, from: PathBuf, to: PathBuf) -> Result<(), RestructureError> {
+        if from == to {
+            return Ok(());
+        }
+        if let Some(reason) = self.conflict(&from, &to) {
+            self.conflicts.push(ConflictingMove { from, to, reason });
+            return Ok(());
+        }
+
+        // Sources exist on disk, so a target that is also a source counts as
+        // taken; chained renames (a -> b, b -> c) need to be split into two plans.
+        let taken = fs::symlink_metadata(&to).is_ok() || self.claimed.contains(&to);
+        let to = if taken {
+            match self.collision {
+                CollisionPolicy::Rename => unique_name(&to, &self.claimed),
+                CollisionPolicy::Skip => {
+                    self.skipped.push(from);
+                    return Ok(());
+                }
+                CollisionPolicy::Fail => {
+                    return Err(RestructureError::Conflict(to.display().to_string()))
+                }
+            }
+        } else {
+            to
+        };
+
+        self.claimed.insert(to.clone());
+        self.sources.insert(from.clone());
+        self.targets.insert(to.clone());
+        self.moves.push(PlannedMove { from, to });
+        Ok(())
+    }
+
+    /// Checks what `apply` would run into beyond a plain name collision.
+    fn conflict(&self, from: &Path, to: &Path) -> Option<MoveConflict> {
+        if contains_related(&self.sources, from)
+            || contains_related(&self.targets, from)
+            || to
+                .ancestors()
+                .any(|ancestor| self.sources.contains(ancestor))
+        {
+            return Some(MoveConflict::NestedSource);
+        }
+
+        let blocked_parent = to
+            .ancestors()
+            .skip(1)
+            .take_while(|ancestor| *ancestor != self.root && ancestor.starts_with(self.root))
+            .any(|ancestor| {
+                self.targets.contains(ancestor)
+                    || fs::metadata(ancestor).is_ok_and(|metadata| !metadata.is_dir())
+            });
+        if blocked_parent || first_below(&self.targets, to).is_some() {
+            return Some(MoveConflict::ParentIsFile);
+        }
+        None
+    }
+
+    fn finish(self, description: String, remove_empty_dirs: bool) -> RestructurePlan {
+        RestructurePlan {
+            description,
+            root: self.root.to_path_buf(),
+            moves: self.moves,
+            skipped: self.skipped,
+            conflicts: self.conflicts,
+            remove_empty_dirs,
+        }
+    }
+}
+
+/// Whether `paths` holds `path`, one of its ancestors or one of its descendants.
+fn contains_related(paths: &BTreeSet<PathBuf>, path: &Path) -> bool {
+    path.ancestors().any(|ancestor| paths.contains(ancestor)) || first_below(paths, path).is_some()
+}
+
+/// A path in `paths` strictly below `path`. Paths order by component, so a
+/// directory's descendants follow it directly.
+fn first_below<'p>(paths: &'p BTreeSet<PathBuf>, path: &Path) -> Option<&'p PathBuf> {
+    paths
+        .range::<Path, _>((Bound::Excluded(path), Bound::Unbounded))
+        .next()
+        .filter(|next| next.starts_with(path))
+}
+
+fn ensure_dir(path: &Path) -> Result<(), RestructureError> {
+    if path.is_dir() {
+        Ok(())
+    } else {
+        Err(RestructureError::InvalidPath(path.display().to_string()))
+    }
+}
+
+/// Files and symlinks below `dir`; symlinked directories are not followed.
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            collect_files(&entry.path(), files)?;
+        } else {
+            files.push(entry.path());
+        }
+    }
+    Ok(())
+}
+
+fn split_folder(file: &Path, metadata: &fs::Metadata, key: SplitKey) -> String {
+    match key {
+        SplitKey::Month => metadata
+            .modified()
+            .map(|modified| {
+                DateTime::<Local>::from(modified)
+                    .format("%Y-%m")
+                    .to_string()
+            })
+            .unwrap_or_else(|_| "Unknown date".to_string()),
+        SplitKey::Extension => file
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_else(|| "No extension".to_string()),
+        SplitKey::InitialLetter => {
+            let initial = file
+                .file_name()
+                .and_then(|name| name.to_string_lossy().chars().next());
+            match initial {
+                Some(c) if c.is_alphabetic() => c.to_uppercase().collect(),
+                Some(c) if c.is_ascii_digit() => "0-9".to_string(),
+                _ => "#".to_string(),
+            }
+        }
+    }
+}
+
+/// Joins a relative mapping path onto `root`, rejecting anything that could leave it.
+fn relative_to(root: &Path, relative: &str) -> Option<PathBuf> {
+    let relative = Path::new(relative.trim());
+    let is_plain = relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    (is_plain && relative.components().next().is_some()).then(|| root.join(relative))
+}
+
+fn create_missing_dirs(dir: &Path, created: &mut Vec<PathBuf>) -> io::Result<()> {
+    let missing: Vec<&Path> = dir
+        .ancestors()
+        .take_while(|ancestor| fs::symlink_metadata(ancestor).is_err())
+        .collect();
+    for dir in missing.into_iter().rev() {
+        fs::create_dir(dir)?;
+        created.push(dir.to_path_buf());
+    }
+    Ok(())
+}
+
+fn revert_moves(done: &[PlannedMove], created_dirs: &[PathBuf]) {
+    for planned in done.iter().rev() {
+        let _ = fs::rename(&planned.to, &planned.from);
+    }
+    for dir in created_dirs.iter().rev() {
+        let _ = fs::remove_dir(dir);
+    }
+}
+
+/// Removes the directories below `root` (not `root` itself) that held a moved
+/// file and are now empty, children first. Directories that were empty before
+/// the moves are left alone, since undo would not bring them back.
+fn remove_emptied_dirs(root: &Path, moves: &[PlannedMove], removed: &mut Vec<PathBuf>) {
+    let emptied: BTreeSet<&Path> = moves
+        .iter()
+        .filter_map(|planned| planned.from.parent())
+        .flat_map(Path::ancestors)
+        .filter(|dir| *dir != root && dir.starts_with(root))
+        .collect();
+    // Descendants sort after their ancestors, so reverse order visits children first.
+    for dir in emptied.into_iter().rev() {
+        if fs::remove_dir(dir).is_ok() {
+            removed.push(dir.to_path_buf());
+        }
+    }
+}
+
+fn journal(app: &tauri::AppHandle) -> Result<UndoJournal, RestructureError> {
+    Ok(UndoJournal::new(
+        app.path().app_data_dir()?.join(JOURNAL_FILE),
+    ))
+}
+
+async fn run_blocking<T, F>(call: F) -> Result<T, RestructureError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, RestructureError> + Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(call)
+        .await
+        .map_err(|err| RestructureError::IoError(err.to_string()))?
+}
+
+#[tauri::command]
+pub async fn plan_flatten_directory(
+    str_path: String,
+    collision: CollisionPolicy,
+) -> Result<RestructurePlan, RestructureError> {
+    run_blocking(move || plan_flatten(Path::new(&str_path), collision)).await
+}
+
+#[tauri::command]
+pub async fn plan_split_directory(
+    str_path: String,
+    split_by: SplitKey,
+) -> Result<RestructurePlan, RestructureError> {
+    run_blocking(move || plan_split(Path::new(&str_path), split_by)).await
+}
+
+#[tauri::command]
+pub async fn plan_restructure_from_mapping(
+    str_path: String,
+    str_mapping_path: String,
+    collision: CollisionPolicy,
+) -> Result<RestructurePlan, RestructureError> {
+    run_blocking(move || {
+        let mapping = fs::read_to_string(&str_mapping_path)?;
+        plan_from_mapping(Path::new(&str_path), &mapping, collision)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn apply_restructure_plan(
+    plan: RestructurePlan,
+    app: tauri::AppHandle,
+) -> Result<JournalEntry, RestructureError> {
+    let journal = journal(&app)?;
+    run_blocking(move || {
+        let entry = apply(&plan)?;
+        journal.push(entry.clone())?;
+        Ok(entry)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn list_restructure_journal(
+    app: tauri::AppHandle,
+) -> Result<Vec<JournalEntry>, RestructureError> {
+    let journal = journal(&app)?;
+    run_blocking(move || journal.entries()).await
+}
+
+#[tauri::command]
+pub async fn undo_restructure(id: u64, app: tauri::AppHandle) -> Result<(), RestructureError> {
+    let journal = journal(&app)?;
+    run_blocking(move || {
+        let entry = journal.take(id)?;
+        if let Err(err) = undo(&entry) {
+            // Keep the entry so the user can fix the conflict and try again.
+            journal.push(entry)?;
+            return Err(err);
+        }
+        Ok(())
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(root: &Path, files: &[&str]) {
+        for name in files {
+            let path = root.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, name).unwrap();
+        }
+    }
+
+    fn reasons(plan: &RestructurePlan) -> Vec<(PathBuf, MoveConflict)> {
+        plan.conflicts
+            .iter()
+            .map(|conflict| (conflict.from.clone(), conflict.reason))
+            .collect()
+    }
+
+    #[test]
+    fn flatten_renames_collisions_and_undo_restores_the_tree() {
+        let root = tempfile::tempdir().unwrap();
+        write(root.path(), &["a/x.txt", "b/x.txt", "b/c/y.txt"]);
+
+        let plan = plan_flatten(root.path(), CollisionPolicy::Rename)
+            .ok()
+            .unwrap();
+        assert!(plan.conflicts.is_empty());
+        let targets: Vec<&Path> = plan
+            .moves
+            .iter()
+            .map(|planned| planned.to.as_path())
+            .collect();
+        assert_eq!(
+            targets,
+            [
+                root.path().join("x.txt"),
+                root.path().join("y.txt"),
+                root.path().join("x (2).txt")
+            ]
+        );
+
+        let entry = apply(&plan).ok().unwrap();
+        assert!(!root.path().join("b").exists());
+        assert_eq!(entry.removed_dirs.len(), 3);
+
+        undo(&entry).ok().unwrap();
+        assert_eq!(
+            fs::read_to_string(root.path().join("b/c/y.txt")).unwrap(),
+            "b/c/y.txt"
+        );
+        assert!(!root.path().join("x.txt").exists());
+    }
+
+    #[test]
+    fn flatten_keeps_directories_that_were_already_empty() {
+        let root = tempfile::tempdir().unwrap();
+        write(root.path(), &["a/x.txt", "a/kept/y.txt"]);
+        fs::create_dir_all(root.path().join("empty/nested")).unwrap();
+        fs::create_dir(root.path().join("a/empty")).unwrap();
+
+        let plan = plan_flatten(root.path(), CollisionPolicy::Rename)
+            .ok()
+            .unwrap();
+        let entry = apply(&plan).ok().unwrap();
+        assert_eq!(entry.removed_dirs, [root.path().join("a/kept")]);
+        assert!(root.path().join("empty/nested").is_dir());
+        assert!(root.path().join("a/empty").is_dir());
+    }
+
+    #[test]
+    fn journal_ids_are_unique() {
+        let ids: HashSet<u64> = (0..1000).map(|_| next_journal_id()).collect();
+        assert_eq!(ids.len(), 1000);
+    }
+
+    #[test]
+    fn split_folder_blocked_by_a_file_is_a_conflict() {
+        let root = tempfile::tempdir().unwrap();
+        write(root.path(), &["a.txt", "txt"]);
+
+        let plan = plan_split(root.path(), SplitKey::Extension).ok().unwrap();
+        assert_eq!(
+            reasons(&plan),
+            [(root.path().join("a.txt"), MoveConflict::ParentIsFile)]
+        );
+        assert_eq!(plan.moves.len(), 1);
+        assert!(matches!(apply(&plan), Err(RestructureError::Conflict(_))));
+        assert!(root.path().join("txt").is_file());
+    }
+
+    #[test]
+    fn mapping_nested_sources_is_a_conflict() {
+        let root = tempfile::tempdir().unwrap();
+        write(root.path(), &["dir/f", "other"]);
+        let mapping = "dir\tmoved\ndir/f\tf\nother\tmoved/inside\n";
+
+        let plan = plan_from_mapping(root.path(), mapping, CollisionPolicy::Fail)
+            .ok()
+            .unwrap();
+        assert_eq!(
+            reasons(&plan),
+            [
+                (root.path().join("dir/f"), MoveConflict::NestedSource),
+                (root.path().join("other"), MoveConflict::ParentIsFile),
+            ]
+        );
+        assert!(matches!(apply(&plan), Err(RestructureError::Conflict(_))));
+        assert!(root.path().join("dir/f").exists());
+    }
+
+    #[test]
+    fn collision_policies() {
+        let root = tempfile::tempdir().unwrap();
+        write(root.path(), &["a", "b"]);
+        let mapping = "a\tb\n";
+
+        let plan = plan_from_mapping(root.path(), mapping, CollisionPolicy::Skip)
+            .ok()
+            .unwrap();
+        assert_eq!(plan.skipped, [root.path().join("a")]);
+        let result = plan_from_mapping(root.path(), mapping, CollisionPolicy::Fail);
+        assert!(matches!(result, Err(RestructureError::Conflict(_))));
+        let result = plan_from_mapping(root.path(), "../a\tb\n", CollisionPolicy::Fail);
+        assert!(matches!(
+            result,
+            Err(RestructureError::InvalidMapping { line: 1, .. })
+        ));
+    }
+}
@@ -0,0 +1,651 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::ipc::Channel;
+
+use crate::operations::{OperationControl, OperationRegistry, ProgressThrottle};
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+const BUFFER_SIZE: usize = 1024 * 1024;
+
+// Content-defined chunks average about 64 KiB, bounded to 16..256 KiB.
+const MIN_CHUNK: usize = 16 * 1024;
+const MAX_CHUNK: usize = 256 * 1024;
+const BOUNDARY_MASK: u64 = 0xffff << 48;
+
+/// Files sharing a chunk are compared with at most this many of the next files
+/// that share it, so a chunk found in many files, such as a long run of zeros,
+/// does not compare every file with every other. Near-copies beyond the limit
+/// still end up in one group through chains of pairs.
+const MAX_CHUNK_PARTNERS: usize = 64;
+
+const GEAR: [u64; 256] = gear_table();
+
+pub enum SimilarityError {
+    InvalidPath(String),
+    InvalidOptions(String),
+    Cancelled,
+    IoError(String),
+}
+
+impl serde::Serialize for SimilarityError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let err_msg = match self {
+            SimilarityError::InvalidPath(path) => format!("Invalid Path: {}", path),
+            SimilarityError::InvalidOptions(reason) => format!("Invalid Options: {}", reason),
+            SimilarityError::Cancelled => "Cancelled".to_string(),
+            SimilarityError::IoError(reason) => format!("IO Error: {}", reason),
+        };
+
+        serializer.serialize_str(err_msg.as_str())
+    }
+}
+
+impl From<io::Error> for SimilarityError {
+    fn from(err: io::Error) -> Self {
+        SimilarityError::IoError(err.to_string())
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SimilarityOptions {
+    /// Share of content, from 0 to 1, two files must have in common to be grouped.
+    pub threshold: f64,
+    /// Smaller files are left to an exact comparison and ignored here.
+    pub min_size: u64,
+}
+
+impl Default for SimilarityOptions {
+    fn default() -> Self {
+        Self {
+            threshold: 0.5,
+            min_size: 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SimilarityPhase {
+    Scanning,
+    Hashing,
+    Comparing,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimilarityProgress {
+    pub phase: SimilarityPhase,
+    pub current_file: PathBuf,
+    pub files_done: u64,
+    pub files_total: u64,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimilarFile {
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+/// Two files of a group, given as indexes into its `files`.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimilarPair {
+    pub first: usize,
+    pub second: usize,
+    pub similarity: f64,
+}
+
+/// Files linked by pairs at or above the threshold. A file can end up in a
+/// group through a chain of pairs without being similar to every other member.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimilarityGroup {
+    pub files: Vec<SimilarFile>,
+    pub pairs: Vec<SimilarPair>,
+}
+
+#[derive(Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimilarityReport {
+    pub groups: Vec<SimilarityGroup>,
+    pub files_scanned: u64,
+    /// Directories and files that could not be read.
+    pub skipped: Vec<PathBuf>,
+}
+
+struct Candidate {
+    path: PathBuf,
+    size: u64,
+}
+
+/// Distinct chunks of a file, keyed by a truncated SHA-256 digest, with their lengths.
+struct Fingerprint {
+    chunks: HashMap<u64, u32>,
+    unique_bytes: u64,
+}
+
+/// Finds near-duplicate files by splitting them into content-defined chunks and
+/// measuring how many bytes of chunks two files share.
+///
+/// Chunk boundaries follow the content rather than fixed offsets, so inserting or
+/// removing data only changes the chunks around the edit. Similarity is the
+/// shared byte count over the union of both files' distinct chunks.
+pub struct SimilarityDetector {
+    control: Arc<OperationControl>,
+}
+
+impl SimilarityDetector {
+    pub fn new(control: Arc<OperationControl>) -> Self {
+        Self { control }
+    }
+
+    pub fn run<F>(
+        &self,
+        root: &Path,
+        options: &SimilarityOptions,
+        on_progress: F,
+    ) -> Result<SimilarityReport, SimilarityError>
+    where
+        F: Fn(SimilarityProgress) + Sync,
+    {
+        if !(0.0..=1.0).contains(&options.threshold) {
+            return Err(SimilarityError::InvalidOptions(format!(
+                "threshold must be between 0 and 1, got {}",
+                options.threshold
+            )));
+        }
+        if !root.is_dir() {
+            return Err(SimilarityError::InvalidPath(root.display().to_string()));
+        }
+
+        let mut report = SimilarityReport::default();
+        let mut candidates = Vec::new();
+        let throttle = ProgressThrottle::new(PROGRESS_INTERVAL);
+        self.scan(
+            root,
+            options.min_size,
+            &mut candidates,
+            &mut report.skipped,
+            &|path, count| {
+                if throttle.ready() {
+                    on_progress(SimilarityProgress {
+                        phase: SimilarityPhase::Scanning,
+                        current_file: path.to_path_buf(),
+                        files_done: 0,
+                        files_total: count,
+                        bytes_done: 0,
+                        bytes_total: 0,
+                    });
+                }
+            },
+        );
+        if self.control.is_cancelled() {
+            return Err(SimilarityError::Cancelled);
+        }
+
+        let files_total = candidates.len() as u64;
+        let bytes_total = candidates.iter().map(|candidate| candidate.size).sum();
+        let files_done = AtomicU64::new(0);
+        let bytes_done = AtomicU64::new(0);
+        let fingerprints: Vec<io::Result<Fingerprint>> = candidates
+            .par_iter()
+            .map(|candidate| {
+                let fingerprint = self.fingerprint(&candidate.path, |read| {
+                    let bytes = bytes_done.fetch_add(read, Ordering::Relaxed) + read;
+                    if throttle.ready() {
+                        on_progress(SimilarityProgress {
+                            phase: SimilarityPhase::Hashing,
+                            current_file: candidate.path.clone(),
+                            files_done: files_done.load(Ordering::Relaxed),
+                            files_total,
+                            bytes_done: bytes,
+                            bytes_total,
+                        });
+                    }
+                });
+                files_done.fetch_add(1, Ordering::Relaxed);
+                fingerprint
+            })
+            .collect();
+        if self.control.is_cancelled() {
+            return Err(SimilarityError::Cancelled);
+        }
+
+        on_progress(SimilarityProgress {
+            phase: SimilarityPhase::Comparing,
+            current_file: root.to_path_buf(),
+            files_done: files_total,
+            files_total,
+            bytes_done: bytes_total,
+            bytes_total,
+        });
+
+        let mut files = Vec::new();
+        let mut prints = Vec::new();
+        for (candidate, fingerprint) in candidates.into_iter().zip(fingerprints) {
+            match fingerprint {
+                Ok(fingerprint) => {
+                    files.push(candidate);
+                    prints.push(fingerprint);
+                }
+                Err(_) => report.skipped.push(candidate.path),
+            }
+        }
+
+        report.files_scanned = files.len() as u64;
+        report.groups = group_pairs(files, similar_pairs(&prints, options.threshold));
+        Ok(report)
+    }
+
+    fn scan(
+        &self,
+        dir: &Path,
+        min_size: u64,
+        candidates: &mut Vec<Candidate>,
+        skipped: &mut Vec<PathBuf>,
+        on_file: &dyn Fn(&Path, u64),
+    ) {
+        if !self.control.checkpoint() {
+            return;
+        }
+        let Ok(entries) = fs::read_dir(dir) else {
+            skipped.push(dir.to_path_buf());
+            return;
+        };
+
+        for entry in entries.flatten() {
+            // DirEntry::metadata does not traverse symlinks.
+            let Ok(metadata) = entry.metadata() else {
+                skipped.push(entry.path());
+                continue;
+            };
+            if metadata.is_dir() {
+                self.scan(&entry.path(), min_size, candidates, skipped, on_file);
+            } else if metadata.is_file() && metadata.len() >= min_size {
+                candidates.push(Candidate {
+                    path: entry.path(),
+                    size: metadata.len(),
+                });
+                on_file(&entry.path(), candidates.len() as u64);
+            }
+        }
+    }
+
+    fn fingerprint(&self, path: &Path, on_read: impl Fn(u64)) -> io::Result<Fingerprint> {
+        let mut file = File::open(path)?;
+        let mut buffer = vec![0; BUFFER_SIZE];
+        let mut chunker = Chunker::default();
+        loop {
+            if !self.control.checkpoint() {
+                return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+            }
+            let read = file.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            chunker.update(&buffer[..read]);
+            on_read(read as u64);
+        }
+        Ok(chunker.finish())
+    }
+}
+
+/// Splits a byte stream at positions picked by a gear rolling hash and hashes
+/// each chunk with SHA-256.
+struct Chunker {
+    hasher: Sha256,
+    rolling: u64,
+    chunk_len: usize,
+    fingerprint: Fingerprint,
+}
+
+impl Default for Chunker {
+    fn default() -> Self {
+        Self {
+            hasher: Sha256::new(),
+            rolling: 0,
+            chunk_len: 0,
+            fingerprint: Fingerprint {
+                chunks: HashMap::new(),
+                unique_bytes: 0,
+            },
+        }
+    }
+}
+
+impl Chunker {
+    fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            // No boundary can fall before MIN_CHUNK, so skip hashing those bytes.
+            let skip = MIN_CHUNK.saturating_sub(self.chunk_len).min(data.len());
+            let mut end = skip;
+            self.chunk_len += skip;
+
+            let mut boundary = false;
+            while end < data.len() {
+                self.rolling = (self.rolling << 1).wrapping_add(GEAR[data[end] as usize]);
+                end += 1;
+                self.chunk_len += 1;
+                if self.rolling & BOUNDARY_MASK == 0 || self.chunk_len >= MAX_CHUNK {
+                    boundary = true;
+                    break;
+                }
+            }
+
+            self.hasher.update(&data[..end]);
+            if boundary {
+                self.cut();
+            }
+            data = &data[end..];
+        }
+    }
+
+    fn cut(&mut self) {
+        let digest = std::mem::take(&mut self.hasher).finalize();
+        let key = u64::from_le_bytes(digest[..8].try_into().unwrap());
+        let len = self.chunk_len as u32;
+        if self.fingerprint.chunks.insert(key, len).is_none() {
+            self.fingerprint.unique_bytes += len as u64;
+        }
+        self.rolling = 0;
+        self.chunk_len = 0;
+    }
+
+    fn finish(mut self) -> Fingerprint {
+        if self.chunk_len > 0 {
+            self.cut();
+        }
+        self.fingerprint
+    }
+}
+
+/// Returns `(first, second, similarity)` for every pair at or above `threshold`.
+fn similar_pairs(prints: &[Fingerprint], threshold: f64) -> Vec<(usize, usize, f64)> {
+    let mut sharers: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (index, print) in prints.iter().enumerate() {
+        for &key in print.chunks.keys() {
+            sharers.entry(key).or_default().push(index);
+        }
+    }
+
+    let mut candidates: HashSet<(usize, usize)> = HashSet::new();
+    for files in sharers.values() {
+        for (position, &first) in files.iter().enumerate() {
+            for &second in files[position + 1..].iter().take(MAX_CHUNK_PARTNERS) {
+                candidates.insert((first, second));
+            }
+        }
+    }
+
+    candidates
+        .into_par_iter()
+        .filter_map(|(first, second)| {
+            let shared = shared_bytes(&prints[first], &prints[second]);
+            let union = prints[first].unique_bytes + prints[second].unique_bytes - shared;
+            let similarity = shared as f64 / union as f64;
+            (similarity >= threshold).then_some((first, second, similarity))
+        })
+        .collect()
+}
+
+/// Bytes of the chunks two files have in common.
+fn shared_bytes(first: &Fingerprint, second: &Fingerprint) -> u64 {
+    let (smaller, larger) = if first.chunks.len() <= second.chunks.len() {
+        (first, second)
+    } else {
+        (second, first)
+    };
+    smaller
+        .chunks
+        .iter()
+        .filter(|(key, _)| larger.chunks.contains_key(key))
+        .map(|(_, len)| *len as u64)
+        .sum()
+}
+
+fn group_pairs(files: Vec<Candidate>, pairs: Vec<(usize, usize, f64)>) -> Vec<SimilarityGroup> {
+    let mut parent: Vec<usize> = (0..files.len()).collect();
+    for &(first, second, _) in &pairs {
+        let (a, b) = (root(&mut parent, first), root(&mut parent, second));
+        parent[a.max(b)] = a.min(b);
+    }
+
+    // Map each file to its group and its position inside it.
+    let mut groups: Vec<SimilarityGroup> = Vec::new();
+    let mut group_of_root: HashMap<usize, usize> = HashMap::new();
+    let mut slot: Vec<Option<(usize, usize)>> = vec![None; files.len()];
+    let mut paired = vec![false; files.len()];
+    for &(first, second, _) in &pairs {
+        paired[first] = true;
+        paired[second] = true;
+    }
+    for (index, file) in files.into_iter().enumerate() {
+        if !paired[index] {
+            continue;
+        }
+        let group_root = root(&mut parent, index);
+        let group = *group_of_root.entry(group_root).or_insert_with(|| {
+            groups.push(SimilarityGroup {
+                files: Vec::new(),
+                pairs: Vec::new(),
+            });
+            groups.len() - 1
+        });
+        slot[index] = Some((group, groups[group].files.len()));
+        groups[group].files.push(SimilarFile {
+            path: file.path,
+            size: file.size,
+        });
+    }
+
+    for (first, second, similarity) in pairs {
+        let (group, first) = slot[first].unwrap();
+        let (_, second) = slot[second].unwrap();
+        groups[group].pairs.push(SimilarPair {
+            first,
+            second,
+            similarity,
+        });
+    }
+
+    for group in &mut groups {
+        group
+            .pairs
+            .sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+    }
+    // Groups that free the most space come first.
+    groups.sort_by_key(|group| {
+        std::cmp::Reverse(group.files.iter().map(|file| file.size).sum::<u64>())
+    });
+    groups
+}
+
+/// Union-find lookup with path halving.
+fn root(parent: &mut [usize], mut index: usize) -> usize {
+    while parent[index] != index {
+        parent[index] = parent[parent[index]];
+        index = parent[index];
+    }
+    index
+}
+
+/// Pseudo-random table for the gear hash, generated with SplitMix64 so it is
+/// the same on every build.
+const fn gear_table() -> [u64; 256] {
+    let mut table = [0; 256];
+    let mut state: u64 = 0;
+    let mut index = 0;
+    while index < 256 {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut value = state;
+        value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        table[index] = value ^ (value >> 31);
+        index += 1;
+    }
+    table
+}
+
+#[tauri::command]
+pub async fn find_similar_files(
+    str_path: String,
+    options: SimilarityOptions,
+    operation_id: String,
+    on_progress: Channel<SimilarityProgress>,
+    registry: tauri::State<'_, OperationRegistry>,
+) -> Result<SimilarityReport, SimilarityError> {
+    let detector = SimilarityDetector::new(registry.register(&operation_id));
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        detector.run(Path::new(&str_path), &options, |progress| {
+            let _ = on_progress.send(progress);
+        })
+    })
+    .await;
+
+    registry.finish(&operation_id);
+    result.map_err(|err| SimilarityError::IoError(err.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic pseudo-random bytes, so chunks do not repeat.
+    fn noise(len: usize, mut seed: u64) -> Vec<u8> {
+        (0..len)
+            .map(|_| {
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                seed as u8
+            })
+            .collect()
+    }
+
+    fn fingerprint(data: &[u8], piece: usize) -> Fingerprint {
+        let mut chunker = Chunker::default();
+        for part in data.chunks(piece) {
+            chunker.update(part);
+        }
+        chunker.finish()
+    }
+
+    #[test]
+    fn chunks_do_not_depend_on_read_sizes() {
+        let data = noise(2 * 1024 * 1024, 1);
+        let whole = fingerprint(&data, data.len());
+        let pieces = fingerprint(&data, 4097);
+        assert_eq!(whole.chunks, pieces.chunks);
+        assert_eq!(whole.unique_bytes, data.len() as u64);
+    }
+
+    #[test]
+    fn chunks_stay_within_bounds() {
+        let data = noise(4 * 1024 * 1024, 2);
+        let print = fingerprint(&data, BUFFER_SIZE);
+        let mut undersized = print
+            .chunks
+            .values()
+            .filter(|len| (**len as usize) < MIN_CHUNK);
+        // Only the trailing chunk may fall short of the minimum.
+        assert!(undersized.nth(1).is_none());
+        assert!(print.chunks.values().all(|len| *len as usize <= MAX_CHUNK));
+        assert!(print.chunks.len() > 1);
+    }
+
+    #[test]
+    fn zeros_are_cut_at_the_maximum() {
+        let print = fingerprint(&vec![0; MAX_CHUNK * 3], BUFFER_SIZE);
+        assert_eq!(print.chunks.len(), 1);
+        assert_eq!(print.unique_bytes, MAX_CHUNK as u64);
+    }
+
+    #[test]
+    fn insertion_only_changes_nearby_chunks() {
+        let original = noise(2 * 1024 * 1024, 3);
+        let mut edited = original.clone();
+        edited.splice(1024 * 1024..1024 * 1024, noise(100, 4));
+
+        let before = fingerprint(&original, BUFFER_SIZE);
+        let after = fingerprint(&edited, BUFFER_SIZE);
+        let shared = shared_bytes(&before, &after);
+        assert!(shared + 2 * MAX_CHUNK as u64 >= before.unique_bytes);
+    }
+
+    /// A fingerprint of `shared` chunks common to every file plus one of its own.
+    fn near_copy(index: u64, shared: u64) -> Fingerprint {
+        let mut chunks: HashMap<u64, u32> = (0..shared).map(|key| (key, 1000)).collect();
+        chunks.insert(u64::MAX - index, 1000);
+        Fingerprint {
+            unique_bytes: (shared + 1) * 1000,
+            chunks,
+        }
+    }
+
+    #[test]
+    fn many_near_copies_form_one_group() {
+        let count = MAX_CHUNK_PARTNERS * 3;
+        let prints: Vec<Fingerprint> = (0..count as u64).map(|index| near_copy(index, 9)).collect();
+        let pairs = similar_pairs(&prints, 0.8);
+        assert!(pairs
+            .iter()
+            .all(|&(_, _, similarity)| (similarity - 9.0 / 11.0).abs() < 1e-9));
+
+        let files = (0..count)
+            .map(|index| Candidate {
+                path: PathBuf::from(format!("copy-{}", index)),
+                size: 10_000,
+            })
+            .collect();
+        let groups = group_pairs(files, pairs);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].files.len(), count);
+    }
+
+    #[test]
+    fn similarity_counts_every_shared_chunk() {
+        let prints = [near_copy(0, 3), near_copy(1, 3), near_copy(2, 1)];
+        let mut pairs = similar_pairs(&prints, 0.0);
+        pairs.sort_by_key(|&(first, second, _)| (first, second));
+        let rounded: Vec<(usize, usize, u64)> = pairs
+            .into_iter()
+            .map(|(first, second, similarity)| {
+                (first, second, (similarity * 1000.0).round() as u64)
+            })
+            .collect();
+        // 3 of 5 distinct chunks, then 1 of 5 and 1 of 5.
+        assert_eq!(rounded, [(0, 1, 600), (0, 2, 200), (1, 2, 200)]);
+    }
+
+    #[test]
+    fn rejects_threshold_outside_unit_range() {
+        let dir = tempfile::tempdir().unwrap();
+        let detector = SimilarityDetector::new(Arc::default());
+        for threshold in [f64::NAN, -0.1, 1.5] {
+            let options = SimilarityOptions {
+                threshold,
+                ..SimilarityOptions::default()
+            };
+            assert!(matches!(
+                detector.run(dir.path(), &options, |_| {}),
+                Err(SimilarityError::InvalidOptions(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use std::cmp::Ordering;
+
+use icu_collator::{CaseFirst, Collator, CollatorOptions, Numeric, Strength};
+use icu_locid::Locale;
+use icu_provider::DataLocale;
+use serde::Deserialize;
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CasePolicy {
+    /// `a` and `A` sort together; ties are broken with uppercase first.
+    #[default]
+    Insensitive,
+    /// Case is significant, uppercase first within the same letter.
+    Sensitive,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SortOptions {
+    /// Compare digit runs by value, so `file2` sorts before `file10`.
+    pub natural: bool,
+    pub case: CasePolicy,
+    pub directories_first: bool,
+    /// BCP 47 tag such as `de` or `sv-SE`. Root collation when unset or unparsable.
+    pub locale: Option<String>,
+}
+
+impl Default for SortOptions {
+    fn default() -> Self {
+        Self {
+            natural: true,
+            case: CasePolicy::Insensitive,
+            directories_first: true,
+            locale: None,
+        }
+    }
+}
+
+/// Compares file names with ICU collation, so accented and non-Latin names
+/// sort the way users of the locale expect instead of by code point.
+///
+/// Names the collator considers equal are ordered byte-wise, which keeps the
+/// order total and stable across runs.
+pub struct NameCollator {
+    collator: Collator,
+    directories_first: bool,
+}
+
+impl NameCollator {
+    pub fn new(options: &SortOptions) -> Self {
+        let mut collator_options = CollatorOptions::new();
+        collator_options.strength = Some(match options.case {
+            CasePolicy::Insensitive => Strength::Secondary,
+            CasePolicy::Sensitive => Strength::Tertiary,
+        });
+        collator_options.case_first = Some(CaseFirst::UpperFirst);
+        collator_options.numeric = Some(if options.natural {
+            Numeric::On
+        } else {
+            Numeric::Off
+        });
+
+        let locale = options
+            .locale
+            .as_deref()
+            .and_then(|tag| tag.parse::<Locale>().ok())
+            .map(|locale| DataLocale::from(&locale))
+            .unwrap_or_default();
+        let collator = Collator::try_new(&locale, collator_options)
+            .or_else(|_| Collator::try_new(&DataLocale::default(), collator_options))
+            .expect("root collation data is compiled in");
+
+        Self {
+            collator,
+            directories_first: options.directories_first,
+        }
+    }
+
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        self.collator.compare(a, b).then_with(|| a.cmp(b))
+    }
+
+    pub fn compare_entries(&self, a: (&str, bool), b: (&str, bool)) -> Ordering {
+        let (a_name, a_is_dir) = a;
+        let (b_name, b_is_dir) = b;
+        if self.directories_first && a_is_dir != b_is_dir {
+            return b_is_dir.cmp(&a_is_dir);
+        }
+        self.compare(a_name, b_name)
+    }
+
+    /// Sorts `items` by the `(name, is_dir)` pair returned from `key`.
+    pub fn sort_entries<T>(&self, items: &mut [T], key: impl Fn(&T) -> (&str, bool)) {
+        items.sort_by(|a, b| self.compare_entries(key(a), key(b)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(options: &SortOptions, names: &[(&str, bool)]) -> Vec<String> {
+        let mut items = names.to_vec();
+        NameCollator::new(options).sort_entries(&mut items, |item| *item);
+        items
+            .into_iter()
+            .map(|(name, _)| name.to_string())
+            .collect()
+    }
+
+    fn names(options: &SortOptions, names: &[&str]) -> Vec<String> {
+        let items: Vec<(&str, bool)> = names.iter().map(|name| (*name, false)).collect();
+        sorted(options, &items)
+    }
+
+    #[test]
+    fn natural_order_compares_numbers_by_value() {
+        let files = ["file10", "file2", "file1"];
+        assert_eq!(
+            names(&SortOptions::default(), &files),
+            ["file1", "file2", "file10"]
+        );
+        let options = SortOptions {
+            natural: false,
+            ..SortOptions::default()
+        };
+        assert_eq!(names(&options, &files), ["file1", "file10", "file2"]);
+    }
+
+    #[test]
+    fn case_only_breaks_ties() {
+        for case in [CasePolicy::Insensitive, CasePolicy::Sensitive] {
+            let options = SortOptions {
+                case,
+                ..SortOptions::default()
+            };
+            assert_eq!(
+                names(&options, &["banana", "apple", "Banana", "Apple"]),
+                ["Apple", "apple", "Banana", "banana"]
+            );
+        }
+    }
+
+    #[test]
+    fn case_policy_sets_whether_case_differs_for_the_collator() {
+        let insensitive = NameCollator::new(&SortOptions::default());
+        assert_eq!(
+            insensitive.collator.compare("readme", "README"),
+            Ordering::Equal
+        );
+        // The byte-wise fallback still keeps the order total.
+        assert_eq!(insensitive.compare("README", "readme"), Ordering::Less);
+
+        let sensitive = NameCollator::new(&SortOptions {
+            case: CasePolicy::Sensitive,
+            ..SortOptions::default()
+        });
+        assert_eq!(
+            sensitive.collator.compare("README", "readme"),
+            Ordering::Less
+        );
+    }
+
+    #[test]
+    fn directories_first_is_optional() {
+        let entries = [("b", false), ("c", true), ("a", false)];
+        assert_eq!(sorted(&SortOptions::default(), &entries), ["c", "a", "b"]);
+        let options = SortOptions {
+            directories_first: false,
+            ..SortOptions::default()
+        };
+        assert_eq!(sorted(&options, &entries), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn non_ascii_names_follow_the_locale() {
+        let files = ["Zebra", "Äpfel", "Banane", "apfel"];
+        assert_eq!(
+            names(&SortOptions::default(), &files),
+            ["apfel", "Äpfel", "Banane", "Zebra"]
+        );
+        // Swedish sorts Ä after Z.
+        let swedish = SortOptions {
+            locale: Some("sv".to_string()),
+            ..SortOptions::default()
+        };
+        assert_eq!(
+            names(&swedish, &files),
+            ["apfel", "Banane", "Zebra", "Äpfel"]
+        );
+        let unparsable = SortOptions {
+            locale: Some("not a locale!".to_string()),
+            ..SortOptions::default()
+        };
+        assert_eq!(
+            names(&unparsable, &files),
+            names(&SortOptions::default(), &files)
+        );
+    }
+}
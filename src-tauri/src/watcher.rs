@@ -0,0 +1,458 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use notify::event::{ModifyKind, RenameMode};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use tauri::ipc::Channel;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(200);
+const DEFAULT_MAX_LATENCY: Duration = Duration::from_secs(2);
+
+pub enum WatchError {
+    InvalidPath(String),
+    AlreadyWatched(String),
+    NotifyError(String),
+}
+
+impl serde::Serialize for WatchError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let err_msg = match self {
+            WatchError::InvalidPath(path) => format!("Invalid Path: {}", path),
+            WatchError::AlreadyWatched(path) => format!("Already Watched: {}", path),
+            WatchError::NotifyError(reason) => format!("Notify Error: {}", reason),
+        };
+
+        serializer.serialize_str(err_msg.as_str())
+    }
+}
+
+impl From<notify::Error> for WatchError {
+    fn from(err: notify::Error) -> Self {
+        WatchError::NotifyError(err.to_string())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WatchEventKind {
+    Created,
+    Modified,
+    Removed,
+    Renamed,
+    /// Events below `path` may have been lost, because the OS queue overflowed
+    /// or the watcher failed. Anything derived from the tree should be rebuilt.
+    Rescan,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchEvent {
+    pub kind: WatchEventKind,
+    pub path: PathBuf,
+    /// Previous path, set for `Renamed` events only.
+    pub from: Option<PathBuf>,
+    /// Why a `Rescan` is needed, when a watcher error caused it.
+    pub reason: Option<String>,
+}
+
+/// Watches directories and delivers debounced, coalesced event batches.
+///
+/// Events are collected until the watched tree has been quiet for the debounce
+/// interval, then sent as one batch with at most one event per path. A tree
+/// that never goes quiet is still flushed once the first event of the batch is
+/// `max_latency` old.
+pub struct WatchManager {
+    debounce: Duration,
+    max_latency: Duration,
+    watchers: Mutex<HashMap<PathBuf, RecommendedWatcher>>,
+}
+
+impl Default for WatchManager {
+    fn default() -> Self {
+        Self::new(DEFAULT_DEBOUNCE, DEFAULT_MAX_LATENCY)
+    }
+}
+
+impl WatchManager {
+    pub fn new(debounce: Duration, max_latency: Duration) -> Self {
+        Self {
+            debounce,
+            max_latency,
+            watchers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn watch(
+        &self,
+        path: &Path,
+        recursive: bool,
+    ) -> Result<UnboundedReceiver<Vec<WatchEvent>>, WatchError> {
+        if !path.is_dir() {
+            return Err(WatchError::InvalidPath(path.display().to_string()));
+        }
+
+        let mut watchers = self.watchers.lock().unwrap();
+        if watchers.contains_key(path) {
+            return Err(WatchError::AlreadyWatched(path.display().to_string()));
+        }
+
+        let (raw_tx, raw_rx) = mpsc::channel();
+        let mut watcher =
+            notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+                let _ = raw_tx.send(res);
+            })?;
+        let mode = if recursive {
+            RecursiveMode::Recursive
+        } else {
+            RecursiveMode::NonRecursive
+        };
+        watcher.watch(path, mode)?;
+
+        let (batch_tx, batch_rx) = unbounded_channel();
+        let (debounce, max_latency) = (self.debounce, self.max_latency);
+        let root = path.to_path_buf();
+        // Ends once the watcher is dropped or the receiver goes away.
+        thread::spawn(move || debounce_events(root, raw_rx, batch_tx, debounce, max_latency));
+
+        watchers.insert(path.to_path_buf(), watcher);
+        Ok(batch_rx)
+    }
+
+    pub fn unwatch(&self, path: &Path) -> bool {
+        self.watchers.lock().unwrap().remove(path).is_some()
+    }
+}
+
+fn debounce_events(
+    root: PathBuf,
+    raw_rx: mpsc::Receiver<notify::Result<notify::Event>>,
+    batch_tx: UnboundedSender<Vec<WatchEvent>>,
+    debounce: Duration,
+    max_latency: Duration,
+) {
+    while let Ok(first) = raw_rx.recv() {
+        let mut batch = Batch::new(root.clone());
+        batch.push_result(first);
+
+        let flush_by = Instant::now() + max_latency;
+        let mut deadline = (Instant::now() + debounce).min(flush_by);
+        loop {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            match raw_rx.recv_timeout(timeout) {
+                Ok(result) => {
+                    batch.push_result(result);
+                    deadline = (Instant::now() + debounce).min(flush_by);
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => break,
+                Err(mpsc::RecvTimeoutError::Disconnected) => return,
+            }
+        }
+
+        let events = batch.into_events();
+        if !events.is_empty() && batch_tx.send(events).is_err() {
+            return;
+        }
+    }
+}
+
+/// Per-path coalescing of raw notify events, preserving first-seen order.
+struct Batch {
+    root: PathBuf,
+    order: Vec<PathBuf>,
+    events: HashMap<PathBuf, WatchEvent>,
+    pending_rename: Option<PathBuf>,
+}
+
+impl Batch {
+    fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            order: Vec::new(),
+            events: HashMap::new(),
+            pending_rename: None,
+        }
+    }
+
+    fn push_result(&mut self, result: notify::Result<notify::Event>) {
+        match result {
+            Ok(event) => self.push(event),
+            Err(err) => {
+                let path = err.paths.first().cloned();
+                self.rescan(path, Some(err.to_string()));
+            }
+        }
+    }
+
+    fn push(&mut self, event: notify::Event) {
+        if event.need_rescan() {
+            if event.paths.is_empty() {
+                self.rescan(None, None);
+            }
+            for path in event.paths {
+                self.rescan(Some(path), None);
+            }
+            return;
+        }
+
+        let mut paths = event.paths.into_iter();
+        match event.kind {
+            EventKind::Create(_) => {
+                paths.for_each(|p| self.record(WatchEventKind::Created, p, None))
+            }
+            EventKind::Remove(_) => {
+                paths.for_each(|p| self.record(WatchEventKind::Removed, p, None))
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
+                if let (Some(from), Some(to)) = (paths.next(), paths.next()) {
+                    self.record(WatchEventKind::Renamed, to, Some(from));
+                }
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+                self.flush_pending_rename();
+                self.pending_rename = paths.next();
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+                if let Some(to) = paths.next() {
+                    match self.pending_rename.take() {
+                        Some(from) => self.record(WatchEventKind::Renamed, to, Some(from)),
+                        None => self.record(WatchEventKind::Created, to, None),
+                    }
+                }
+            }
+            EventKind::Modify(_) | EventKind::Any | EventKind::Other => {
+                paths.for_each(|p| self.record(WatchEventKind::Modified, p, None))
+            }
+            EventKind::Access(_) => {}
+        }
+    }
+
+    /// Reports that events below `path`, or the whole tree, may be missing.
+    fn rescan(&mut self, path: Option<PathBuf>, reason: Option<String>) {
+        let path = path.unwrap_or_else(|| self.root.clone());
+        self.record(WatchEventKind::Rescan, path.clone(), None);
+        if let Some(event) = self.events.get_mut(&path) {
+            event.reason = reason.or(event.reason.take());
+        }
+    }
+
+    /// A rename source without a matching target means the file left the watched tree.
+    fn flush_pending_rename(&mut self) {
+        if let Some(from) = self.pending_rename.take() {
+            self.record(WatchEventKind::Removed, from, None);
+        }
+    }
+
+    fn record(&mut self, kind: WatchEventKind, path: PathBuf, from: Option<PathBuf>) {
+        let Some(previous) = self.events.get(&path).map(|event| event.kind) else {
+            self.order.push(path.clone());
+            self.events.insert(
+                path.clone(),
+                WatchEvent {
+                    kind,
+                    path,
+                    from,
+                    reason: None,
+                },
+            );
+            return;
+        };
+
+        let merged = match (previous, kind) {
+            (WatchEventKind::Rescan, _) => WatchEventKind::Rescan,
+            // Created and removed within one batch: nothing to report.
+            (WatchEventKind::Created, WatchEventKind::Removed) => {
+                self.events.remove(&path);
+                return;
+            }
+            (WatchEventKind::Created, WatchEventKind::Modified) => WatchEventKind::Created,
+            (WatchEventKind::Renamed, WatchEventKind::Modified) => WatchEventKind::Renamed,
+            (WatchEventKind::Removed, WatchEventKind::Created) => WatchEventKind::Modified,
+            (_, next) => next,
+        };
+
+        let event = self.events.get_mut(&path).unwrap();
+        event.kind = merged;
+        if merged != WatchEventKind::Renamed {
+            // The rename source stays gone even though this path's event no
+            // longer says where it came from.
+            if let Some(source) = event.from.take() {
+                self.record(WatchEventKind::Removed, source, None);
+            }
+        } else if from.is_some() {
+            event.from = from;
+        }
+    }
+
+    fn into_events(mut self) -> Vec<WatchEvent> {
+        self.flush_pending_rename();
+        self.order
+            .into_iter()
+            .filter_map(|path| self.events.remove(&path))
+            .collect()
+    }
+}
+
+#[tauri::command]
+pub fn watch_directory(
+    str_path: &str,
+    recursive: bool,
+    on_events: Channel<Vec<WatchEvent>>,
+    manager: tauri::State<WatchManager>,
+) -> Result<(), WatchError> {
+    let mut batches = manager.watch(Path::new(str_path), recursive)?;
+    tauri::async_runtime::spawn(async move {
+        while let Some(batch) = batches.recv().await {
+            if on_events.send(batch).is_err() {
+                break;
+            }
+        }
+    });
+    Ok(())
+}
+
+#[tauri::command]
+pub fn unwatch_directory(str_path: &str, manager: tauri::State<WatchManager>) -> bool {
+    manager.unwatch(Path::new(str_path))
+}
+
+#[cfg(test)]
+mod tests {
+    use notify::event::{CreateKind, Flag, RemoveKind};
+
+    use super::*;
+
+    fn event(kind: EventKind, paths: &[&str]) -> notify::Event {
+        paths.iter().fold(notify::Event::new(kind), |event, path| {
+            event.add_path(PathBuf::from(path))
+        })
+    }
+
+    fn summary(batch: Batch) -> Vec<(WatchEventKind, PathBuf, Option<PathBuf>)> {
+        batch
+            .into_events()
+            .into_iter()
+            .map(|event| (event.kind, event.path, event.from))
+            .collect()
+    }
+
+    #[test]
+    fn created_then_removed_is_dropped() {
+        let mut batch = Batch::new(PathBuf::from("/root"));
+        batch.push(event(EventKind::Create(CreateKind::File), &["/root/a"]));
+        batch.push(event(EventKind::Modify(ModifyKind::Any), &["/root/a"]));
+        batch.push(event(EventKind::Remove(RemoveKind::File), &["/root/a"]));
+        assert!(summary(batch).is_empty());
+    }
+
+    #[test]
+    fn removed_then_created_is_modified() {
+        let mut batch = Batch::new(PathBuf::from("/root"));
+        batch.push(event(EventKind::Remove(RemoveKind::File), &["/root/a"]));
+        batch.push(event(EventKind::Create(CreateKind::File), &["/root/a"]));
+        assert_eq!(
+            summary(batch),
+            vec![(WatchEventKind::Modified, PathBuf::from("/root/a"), None)]
+        );
+    }
+
+    #[test]
+    fn rename_halves_are_paired() {
+        let mut batch = Batch::new(PathBuf::from("/root"));
+        let rename = |mode| EventKind::Modify(ModifyKind::Name(mode));
+        batch.push(event(rename(RenameMode::From), &["/root/a"]));
+        batch.push(event(rename(RenameMode::To), &["/root/b"]));
+        batch.push(event(rename(RenameMode::From), &["/root/c"]));
+        assert_eq!(
+            summary(batch),
+            vec![
+                (
+                    WatchEventKind::Renamed,
+                    PathBuf::from("/root/b"),
+                    Some(PathBuf::from("/root/a"))
+                ),
+                (WatchEventKind::Removed, PathBuf::from("/root/c"), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn removing_a_rename_target_keeps_the_source_removed() {
+        let mut batch = Batch::new(PathBuf::from("/root"));
+        let rename = |mode| EventKind::Modify(ModifyKind::Name(mode));
+        batch.push(event(rename(RenameMode::From), &["/root/a"]));
+        batch.push(event(rename(RenameMode::To), &["/root/b"]));
+        batch.push(event(EventKind::Remove(RemoveKind::File), &["/root/b"]));
+        assert_eq!(
+            summary(batch),
+            vec![
+                (WatchEventKind::Removed, PathBuf::from("/root/b"), None),
+                (WatchEventKind::Removed, PathBuf::from("/root/a"), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn overflow_without_paths_rescans_the_root() {
+        let mut batch = Batch::new(PathBuf::from("/root"));
+        batch.push(event(EventKind::Create(CreateKind::File), &["/root/a"]));
+        batch.push(notify::Event::new(EventKind::Other).set_flag(Flag::Rescan));
+        batch.push(event(EventKind::Remove(RemoveKind::Any), &["/root"]));
+        assert_eq!(
+            summary(batch),
+            vec![
+                (WatchEventKind::Created, PathBuf::from("/root/a"), None),
+                (WatchEventKind::Rescan, PathBuf::from("/root"), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_become_rescans_with_a_reason() {
+        let mut batch = Batch::new(PathBuf::from("/root"));
+        batch.push_result(Err(notify::Error::generic("queue overflow")));
+        let events = batch.into_events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, WatchEventKind::Rescan);
+        assert_eq!(events[0].path, PathBuf::from("/root"));
+        assert_eq!(events[0].reason.as_deref(), Some("queue overflow"));
+    }
+
+    #[test]
+    fn busy_tree_is_flushed_after_max_latency() {
+        let (raw_tx, raw_rx) = mpsc::channel();
+        let (batch_tx, mut batch_rx) = unbounded_channel();
+        thread::spawn(move || {
+            debounce_events(
+                PathBuf::from("/root"),
+                raw_rx,
+                batch_tx,
+                Duration::from_millis(100),
+                Duration::from_millis(200),
+            )
+        });
+
+        let started = Instant::now();
+        let writer = thread::spawn(move || {
+            while started.elapsed() < Duration::from_secs(3) {
+                let modify = event(EventKind::Modify(ModifyKind::Any), &["/root/log"]);
+                if raw_tx.send(Ok(modify)).is_err() {
+                    break;
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
+        });
+
+        assert!(batch_rx.blocking_recv().is_some());
+        assert!(started.elapsed() < Duration::from_secs(2));
+        drop(batch_rx);
+        writer.join().unwrap();
+    }
+}